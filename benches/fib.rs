@@ -0,0 +1,16 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use lithp::Interpreter;
+
+// The definition from examples/fib.lthp
+const FIB: &str = "=(fib func(num ifElse(<(num 2) num +(fib(-(num 1)) fib(-(num 2))))))";
+
+fn fib(c: &mut Criterion) {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str(FIB).unwrap();
+    c.bench_function("fib 20", |b| {
+        b.iter(|| interpreter.eval_str("fib(20)").unwrap())
+    });
+}
+
+criterion_group!(benches, fib);
+criterion_main!(benches);
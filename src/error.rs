@@ -0,0 +1,192 @@
+use crate::parse::Span;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LithpError {
+    Arity {
+        expected: String,
+        got: usize,
+        span: Span,
+    },
+    TypeMismatch {
+        message: String,
+        span: Span,
+    },
+    UndeclaredVariable {
+        name: String,
+        span: Span,
+    },
+    UndeclaredFunction {
+        name: String,
+        span: Span,
+    },
+    NotAFunction {
+        name: String,
+        span: Span,
+    },
+    ParseError {
+        message: String,
+        span: Span,
+    },
+    Runtime {
+        message: String,
+        span: Span,
+    },
+    Io {
+        message: String,
+        span: Span,
+    },
+    Overflow {
+        span: Span,
+    },
+    DivideByZero {
+        span: Span,
+    },
+    /// Several errors found in one pass, such as every syntax error in a file
+    Multiple {
+        errors: Vec<LithpError>,
+    },
+}
+
+impl LithpError {
+    pub fn arity<S: ToString>(expected: S, got: usize, span: Span) -> LithpError {
+        LithpError::Arity {
+            expected: expected.to_string(),
+            got,
+            span,
+        }
+    }
+
+    pub fn type_mismatch<S: Into<String>>(message: S, span: Span) -> LithpError {
+        LithpError::TypeMismatch {
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn parse<S: Into<String>>(message: S, span: Span) -> LithpError {
+        LithpError::ParseError {
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            LithpError::Arity { span, .. }
+            | LithpError::TypeMismatch { span, .. }
+            | LithpError::UndeclaredVariable { span, .. }
+            | LithpError::UndeclaredFunction { span, .. }
+            | LithpError::NotAFunction { span, .. }
+            | LithpError::ParseError { span, .. }
+            | LithpError::Runtime { span, .. }
+            | LithpError::Io { span, .. }
+            | LithpError::Overflow { span }
+            | LithpError::DivideByZero { span } => *span,
+            LithpError::Multiple { errors } => {
+                errors.first().map_or_else(Span::default, LithpError::span)
+            }
+        }
+    }
+
+    /// Places the error at `span` if it has no position of its own, as when a host function
+    /// returns it.
+    pub(crate) fn or_span(mut self, span: Span) -> LithpError {
+        match &mut self {
+            LithpError::Arity { span: own, .. }
+            | LithpError::TypeMismatch { span: own, .. }
+            | LithpError::UndeclaredVariable { span: own, .. }
+            | LithpError::UndeclaredFunction { span: own, .. }
+            | LithpError::NotAFunction { span: own, .. }
+            | LithpError::ParseError { span: own, .. }
+            | LithpError::Runtime { span: own, .. }
+            | LithpError::Io { span: own, .. }
+            | LithpError::Overflow { span: own }
+            | LithpError::DivideByZero { span: own } => {
+                if *own == Span::default() {
+                    *own = span;
+                }
+            }
+            LithpError::Multiple { errors } => {
+                let placed = std::mem::take(errors).into_iter().map(|err| err.or_span(span));
+                *errors = placed.collect();
+            }
+        }
+        self
+    }
+
+    fn message(&self) -> String {
+        match self {
+            LithpError::Arity { expected, got, .. } => format!(
+                "Wrong number of arguments; expected {}, got {}",
+                expected, got
+            ),
+            LithpError::TypeMismatch { message, .. }
+            | LithpError::ParseError { message, .. }
+            | LithpError::Runtime { message, .. }
+            | LithpError::Io { message, .. } => message.clone(),
+            LithpError::UndeclaredVariable { name, .. } => {
+                format!("Undeclared variable {}", name)
+            }
+            LithpError::UndeclaredFunction { name, .. } => {
+                format!("Undeclared function {}", name)
+            }
+            LithpError::NotAFunction { name, .. } => format!("{} is not a function", name),
+            LithpError::Overflow { .. } => "Integer overflow".to_owned(),
+            LithpError::DivideByZero { .. } => "Division by zero".to_owned(),
+            LithpError::Multiple { errors } => errors
+                .iter()
+                .map(LithpError::message)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Formats the error as `file:line:col: message`, followed by the offending line of `source`
+    /// with the span underlined. `source` must be the text the span is in; `Interpreter` keeps
+    /// track of that for errors from code it ran.
+    pub fn render(&self, file: &str, source: &str) -> String {
+        if let LithpError::Multiple { errors } = self {
+            return errors
+                .iter()
+                .map(|err| err.render(file, source))
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        let span = self.span();
+        if span.line == 0 {
+            return format!("{}: {}", file, self.message());
+        }
+        let mut rendered = format!("{}:{}:{}: {}", file, span.line, span.col, self.message());
+        if let Some(line) = source.lines().nth(span.line.wrapping_sub(1)) {
+            let gutter = " ".repeat(span.line.to_string().len());
+            rendered += &format!(
+                "\n{} |\n{} | {}\n{} | {}{}",
+                gutter,
+                span.line,
+                line,
+                gutter,
+                " ".repeat(span.col.saturating_sub(1)),
+                "^".repeat(span.len.max(1))
+            );
+        }
+        rendered
+    }
+}
+
+impl fmt::Display for LithpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let LithpError::Multiple { errors } = self {
+            let errors = errors.iter().map(|err| err.to_string()).collect::<Vec<_>>();
+            return write!(f, "{}", errors.join("\n"));
+        }
+        let span = self.span();
+        // Errors from outside any source, such as failing to read a file, have no position
+        if span.line == 0 {
+            return write!(f, "{}", self.message());
+        }
+        write!(f, "{}:{}: {}", span.line, span.col, self.message())
+    }
+}
+
+impl std::error::Error for LithpError {}
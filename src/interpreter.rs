@@ -1,569 +1,1620 @@
-use crate::parse::Expr;
+use crate::error::LithpError;
+use crate::number;
+use crate::parse::{is_integer, parse_source, Expr, ExprKind, Span};
+use crate::value::{Dict, Key, List, Value};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::rc::{Rc, Weak};
 
-pub fn run(exprs: Vec<Expr>) -> Result<(), String> {
-    let mut builtin_funcs: HashMap<
-        String,
-        fn(&mut Environment, &Vec<Expr>) -> Result<Expr, String>,
-    > = HashMap::new();
+/// Runs `exprs` in `env` and returns the value of the last one, or nil if there are none.
+fn run_in(env: &mut Environment, exprs: &[Expr]) -> Result<Value, LithpError> {
+    let mut last = Value::Nil;
+    for expr in exprs {
+        last = eval_non_literal(env, expr)?;
+    }
+    Ok(last)
+}
+
+fn builtin_funcs() -> HashMap<String, BuiltinFn> {
+    let mut builtin_funcs: HashMap<String, BuiltinFn> = HashMap::new();
     builtin_funcs.insert(
-        "=".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            if args.len() != 2 {
-                return Err(format!(
-                    "Wrong number of arguments; expected 2, got {}",
-                    args.len()
-                ));
-            }
-            let name = match &args[0] {
-                Expr::Identifier(name) => name.clone(),
-                _ => return Err("Expected identifier for argument 0, did not get identifier".to_owned()),
-            };
-            let value = eval_non_literal(env, &args[1].clone())?;
-            env.vars.insert(name, value.clone());
-            Ok(value)
+        "print".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            println!("{}", to_string(&args[0].value));
+            Ok(Value::Nil)
         },
     );
     builtin_funcs.insert(
-        "func".to_owned(),
-        |_env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            if args.len() < 1 {
-                return Err(format!(
-                    "Too little arguments; expected 1 or more, got {}",
-                    args.len()
-                ));
+        "*".to_owned(),
+        |_env: &mut Environment, args: &[Arg], _span: Span| -> Result<Value, LithpError> {
+            let mut product = Value::Int(1);
+            for arg in args {
+                product = number::multiply(product, number_arg(arg)?, arg.span)?;
             }
-            for arg_idx in 0..args.len() - 1 {
-                match args[arg_idx] {
-                    Expr::Identifier(_) => (),
-                    _ => {
-                        return Err(format!(
-                            "Expected identifier for argument {}, did not get identifier",
-                            arg_idx
-                        ))
-                    }
-                }
+            Ok(product)
+        },
+    );
+    builtin_funcs.insert(
+        "/".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.is_empty() {
+                return Err(LithpError::arity("1 or more", args.len(), span));
+            }
+            let mut quotient = number_arg(&args[0])?;
+            for arg in &args[1..] {
+                quotient = number::divide(quotient, number_arg(arg)?, arg.span)?;
+            }
+            Ok(quotient)
+        },
+    );
+    builtin_funcs.insert(
+        "+".to_owned(),
+        |_env: &mut Environment, args: &[Arg], _span: Span| -> Result<Value, LithpError> {
+            let mut sum = Value::Int(0);
+            for arg in args {
+                sum = number::add(sum, number_arg(arg)?, arg.span)?;
+            }
+            Ok(sum)
+        },
+    );
+    builtin_funcs.insert(
+        "-".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.is_empty() {
+                return Err(LithpError::arity("1 or more", args.len(), span));
+            }
+            let mut difference = number_arg(&args[0])?;
+            for arg in &args[1..] {
+                difference = number::subtract(difference, number_arg(arg)?, arg.span)?;
+            }
+            Ok(difference)
+        },
+    );
+    builtin_funcs.insert(
+        "numerator".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
             }
-            match args.last().unwrap() {
-                Expr::FuncCall(_, _) => (),
+            match number::to_ratio(&args[0].value) {
+                Some(ratio) => Ok(number::from_bigint(ratio.numer().clone())),
+                None => Err(LithpError::type_mismatch(
+                    "Not an integer or rational value",
+                    args[0].span,
+                )),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "denominator".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            match number::to_ratio(&args[0].value) {
+                Some(ratio) => Ok(number::from_bigint(ratio.denom().clone())),
+                None => Err(LithpError::type_mismatch(
+                    "Not an integer or rational value",
+                    args[0].span,
+                )),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "toInt".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            match integer_arg(&args[0])?.to_i32() {
+                Some(int) => Ok(Value::Int(int)),
+                None => Err(LithpError::Overflow { span: args[0].span }),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "toLong".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            match integer_arg(&args[0])?.to_i64() {
+                Some(long) => Ok(Value::Long(long)),
+                None => Err(LithpError::Overflow { span: args[0].span }),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "toDouble".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let value = &args[0].value;
+            let double = match value {
+                Value::String(string) => string.trim().parse::<f64>().ok(),
+                _ if number::is_number(value) => number::to_double(value),
                 _ => {
-                    return Err(format!(
-                        "Expected function call for argument {}, did not get function call",
-                        args.len() - 1
+                    return Err(LithpError::type_mismatch(
+                        "Expected a number or string",
+                        args[0].span,
                     ))
                 }
+            };
+            match double {
+                Some(double) => Ok(Value::Double(double)),
+                None => Err(LithpError::Runtime {
+                    message: format!("Cannot convert {} to a double", to_string(value)),
+                    span: args[0].span,
+                }),
             }
-            Ok(Expr::FuncCall("func".to_owned(), args.clone()))
         },
     );
     builtin_funcs.insert(
-        "print".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
+        "toString".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
             if args.len() != 1 {
-                return Err(format!(
-                    "Wrong number of arguments; expected 1, got {}",
-                    args.len()
-                ));
+                return Err(LithpError::arity(1, args.len(), span));
             }
-            let value = eval_non_literal(env, &args[0])?;
-            println!("{}", to_string(&value)?);
-            Ok(Expr::StringLiteral(to_string(&value)?))
+            Ok(Value::String(to_string(&args[0].value)))
         },
     );
     builtin_funcs.insert(
-        "*".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            let change_if_larger = |curr_largest: &mut Expr, poss_largest: &Expr| {
-                match curr_largest {
-                    Expr::DoubleLiteral(_) => (),
-                    Expr::LongLiteral(_) => if let Expr::DoubleLiteral(_) = poss_largest {
-                        *curr_largest = poss_largest.clone();
-                    }
-                    Expr::IntLiteral(_) => {
-                        if let Expr::LongLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                        if let Expr::DoubleLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                    }
-                    // Not possible
-                    _ => (),
-                }
-            };
-            let mut largest_value = Expr::IntLiteral(1);
-            let mut product = 1.0;
-            for arg in args {
-                match eval_non_literal(env, &arg) {
-                    Ok(Expr::IntLiteral(int)) => {
-                        change_if_larger(&mut largest_value, &Expr::IntLiteral(1));
-                        product *= int as f64;
-                    }
-                    Ok(Expr::LongLiteral(long)) => {
-                        change_if_larger(&mut largest_value, &Expr::LongLiteral(1));
-                        product *= long as f64;
-                    }
-                    Ok(Expr::DoubleLiteral(double)) => {
-                        change_if_larger(&mut largest_value, &Expr::DoubleLiteral(1.0));
-                        product *= double;
-                    }
-                    Ok(_) => return Err("Not a numeric value".to_owned()),
-                    Err(err) => return Err(err),
-                }
+        "toBool".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            match &args[0].value {
+                Value::Boolean(boolean) => Ok(Value::Boolean(*boolean)),
+                Value::String(string) => match string.trim() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => Err(LithpError::Runtime {
+                        message: format!("Cannot convert {} to a boolean", string),
+                        span: args[0].span,
+                    }),
+                },
+                _ => Err(LithpError::type_mismatch(
+                    "Expected a boolean or string",
+                    args[0].span,
+                )),
             }
-            Ok(match largest_value {
-                Expr::IntLiteral(_) => Expr::IntLiteral(product as i32),
-                Expr::LongLiteral(_) => Expr::LongLiteral(product as i64),
-                Expr::DoubleLiteral(_) => Expr::DoubleLiteral(product),
-                _ => return Err("Not a numeric value".to_owned()),
-            })
         },
     );
     builtin_funcs.insert(
-        "/".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            let change_if_larger = |curr_largest: &mut Expr, poss_largest: &Expr| {
-                match curr_largest {
-                    Expr::DoubleLiteral(_) => (),
-                    Expr::LongLiteral(_) => if let Expr::DoubleLiteral(_) = poss_largest {
-                        *curr_largest = poss_largest.clone();
-                    }
-                    Expr::IntLiteral(_) => {
-                        if let Expr::LongLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                        if let Expr::DoubleLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                    }
-                    // Not possible
-                    _ => (),
-                }
-            };
-            let mut largest_value = Expr::IntLiteral(1);
-            let mut quotient = match eval_non_literal(env, &args[0])? {
-                Expr::DoubleLiteral(double) => double,
-                Expr::LongLiteral(long) => long as f64,
-                Expr::IntLiteral(int) => int as f64,
-                _ => return Err("Not a numeric value".to_owned()),
-            };
-            for arg in &args[1..] {
-                match eval_non_literal(env, &arg) {
-                    Ok(Expr::IntLiteral(int)) => {
-                        change_if_larger(&mut largest_value, &Expr::IntLiteral(1));
-                        quotient /= int as f64;
-                    }
-                    Ok(Expr::LongLiteral(long)) => {
-                        change_if_larger(&mut largest_value, &Expr::LongLiteral(1));
-                        quotient /= long as f64;
-                    }
-                    Ok(Expr::DoubleLiteral(double)) => {
-                        change_if_larger(&mut largest_value, &Expr::DoubleLiteral(1.0));
-                        quotient /= double;
-                    }
-                    Ok(_) => return Err("Not a numeric value".to_owned()),
-                    Err(err) => return Err(err),
-                }
+        "==".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.is_empty() {
+                return Err(LithpError::arity("1 or more", args.len(), span));
+            }
+            let all_equal = args
+                .windows(2)
+                .all(|pair| equal(&pair[0].value, &pair[1].value));
+            Ok(Value::Boolean(all_equal))
+        },
+    );
+    builtin_funcs.insert(
+        ">".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            compare_chain(args, span, |ordering| ordering == Ordering::Greater)
+        },
+    );
+    builtin_funcs.insert(
+        "<".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            compare_chain(args, span, |ordering| ordering == Ordering::Less)
+        },
+    );
+    builtin_funcs.insert(
+        "!=".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
             }
-            Ok(match largest_value {
-                Expr::IntLiteral(_) => Expr::IntLiteral(quotient as i32),
-                Expr::LongLiteral(_) => Expr::LongLiteral(quotient as i64),
-                Expr::DoubleLiteral(_) => Expr::DoubleLiteral(quotient),
-                _ => return Err("Not a numeric value".to_owned()),
+            Ok(Value::Boolean(!equal(&args[0].value, &args[1].value)))
+        },
+    );
+    builtin_funcs.insert(
+        ">=".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            compare_chain(args, span, |ordering| ordering != Ordering::Less)
+        },
+    );
+    builtin_funcs.insert(
+        "<=".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            compare_chain(args, span, |ordering| ordering != Ordering::Greater)
+        },
+    );
+    builtin_funcs.insert(
+        "not".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            Ok(Value::Boolean(!bool_arg(&args[0])?))
+        },
+    );
+    builtin_funcs.insert(
+        "typeOf".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            Ok(Value::String(type_of(&args[0].value).to_owned()))
+        },
+    );
+    builtin_funcs.insert(
+        "isInt".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Int(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isLong".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Long(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isBigInt".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::BigInt(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isRational".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Rational(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isDouble".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Double(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isNumber".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, number::is_number)
+        },
+    );
+    builtin_funcs.insert(
+        "isString".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::String(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isBool".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Boolean(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isFunc".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| {
+                matches!(value, Value::Closure(_) | Value::Builtin(_))
             })
         },
     );
     builtin_funcs.insert(
-        "+".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            let change_if_larger = |curr_largest: &mut Expr, poss_largest: &Expr| {
-                match curr_largest {
-                    Expr::DoubleLiteral(_) => (),
-                    Expr::LongLiteral(_) => if let Expr::DoubleLiteral(_) = poss_largest {
-                        *curr_largest = poss_largest.clone();
-                    }
-                    Expr::IntLiteral(_) => {
-                        if let Expr::LongLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                        if let Expr::DoubleLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                    }
-                    // Not possible
-                    _ => (),
+        "isList".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::List(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isDict".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Dict(_)))
+        },
+    );
+    builtin_funcs.insert(
+        "isNil".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            is_type(args, span, |value| matches!(value, Value::Nil))
+        },
+    );
+    builtin_funcs.insert(
+        "list".to_owned(),
+        |_env: &mut Environment, args: &[Arg], _span: Span| -> Result<Value, LithpError> {
+            let items = args.iter().map(|arg| arg.value.clone()).collect();
+            Ok(Value::List(items))
+        },
+    );
+    builtin_funcs.insert(
+        "cons".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let mut items = list_arg(&args[1])?.clone();
+            items.push_front(args[0].value.clone());
+            Ok(Value::List(items))
+        },
+    );
+    builtin_funcs.insert(
+        "head".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            match list_arg(&args[0])?.front() {
+                Some(head) => Ok(head.clone()),
+                None => Err(LithpError::Runtime {
+                    message: "Cannot take the head of an empty list".to_owned(),
+                    span: args[0].span,
+                }),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "tail".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let mut items = list_arg(&args[0])?.clone();
+            match items.pop_front() {
+                Some(_) => Ok(Value::List(items)),
+                None => Err(LithpError::Runtime {
+                    message: "Cannot take the tail of an empty list".to_owned(),
+                    span: args[0].span,
+                }),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "len".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let len = match &args[0].value {
+                Value::List(items) => items.len(),
+                Value::String(string) => string.chars().count(),
+                _ => {
+                    return Err(LithpError::type_mismatch(
+                        "Not a list or string",
+                        args[0].span,
+                    ))
                 }
             };
-            let mut largest_value = Expr::IntLiteral(1);
-            let mut sum = 0.0;
+            Ok(number::from_bigint(len.into()))
+        },
+    );
+    builtin_funcs.insert(
+        "nth".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let items = list_arg(&args[0])?;
+            let idx = index_arg(&args[1])?;
+            match items.get(idx) {
+                Some(item) => Ok(item.clone()),
+                None => Err(LithpError::Runtime {
+                    message: format!("Index {} is out of bounds for length {}", idx, items.len()),
+                    span: args[1].span,
+                }),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "append".to_owned(),
+        |_env: &mut Environment, args: &[Arg], _span: Span| -> Result<Value, LithpError> {
+            let mut items = List::new();
             for arg in args {
-                match eval_non_literal(env, &arg) {
-                    Ok(Expr::IntLiteral(int)) => {
-                        change_if_larger(&mut largest_value, &Expr::IntLiteral(1));
-                        sum += int as f64;
-                    }
-                    Ok(Expr::LongLiteral(long)) => {
-                        change_if_larger(&mut largest_value, &Expr::LongLiteral(1));
-                        sum += long as f64;
-                    }
-                    Ok(Expr::DoubleLiteral(double)) => {
-                        change_if_larger(&mut largest_value, &Expr::DoubleLiteral(1.0));
-                        sum += double;
-                    }
-                    Ok(_) => return Err("Not a numeric value".to_owned()),
-                    Err(err) => return Err(err),
-                }
+                items.append(list_arg(arg)?.clone());
             }
-            Ok(match largest_value {
-                Expr::IntLiteral(_) => Expr::IntLiteral(sum as i32),
-                Expr::LongLiteral(_) => Expr::LongLiteral(sum as i64),
-                Expr::DoubleLiteral(_) => Expr::DoubleLiteral(sum),
-                _ => return Err("Not a numeric value".to_owned()),
-            })
+            Ok(Value::List(items))
         },
     );
     builtin_funcs.insert(
-        "-".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            let change_if_larger = |curr_largest: &mut Expr, poss_largest: &Expr| {
-                match curr_largest {
-                    Expr::DoubleLiteral(_) => (),
-                    Expr::LongLiteral(_) => if let Expr::DoubleLiteral(_) = poss_largest {
-                        *curr_largest = poss_largest.clone();
-                    }
-                    Expr::IntLiteral(_) => {
-                        if let Expr::LongLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
-                        if let Expr::DoubleLiteral(_) = poss_largest {
-                            *curr_largest = poss_largest.clone();
-                        }
+        "reverse".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let items = list_arg(&args[0])?;
+            Ok(Value::List(items.iter().rev().cloned().collect()))
+        },
+    );
+    builtin_funcs.insert(
+        "isEmpty".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            Ok(Value::Boolean(list_arg(&args[0])?.is_empty()))
+        },
+    );
+    builtin_funcs.insert(
+        "map".to_owned(),
+        |env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let items = list_arg(&args[1])?;
+            let mut mapped = List::new();
+            for item in items {
+                mapped.push_back(call_value(env, &args[0].value, vec![item.clone()], args[0].span)?);
+            }
+            Ok(Value::List(mapped))
+        },
+    );
+    builtin_funcs.insert(
+        "filter".to_owned(),
+        |env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let items = list_arg(&args[1])?;
+            let mut kept = List::new();
+            for item in items {
+                match call_value(env, &args[0].value, vec![item.clone()], args[0].span)? {
+                    Value::Boolean(true) => kept.push_back(item.clone()),
+                    Value::Boolean(false) => (),
+                    _ => {
+                        return Err(LithpError::type_mismatch(
+                            "Expected the filter function to return a boolean",
+                            args[0].span,
+                        ))
                     }
-                    // Not possible
-                    _ => (),
                 }
+            }
+            Ok(Value::List(kept))
+        },
+    );
+    builtin_funcs.insert(
+        "reduce".to_owned(),
+        |env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 3 {
+                return Err(LithpError::arity(3, args.len(), span));
+            }
+            let mut acc = args[1].value.clone();
+            for item in list_arg(&args[2])? {
+                acc = call_value(env, &args[0].value, vec![acc, item.clone()], args[0].span)?;
+            }
+            Ok(acc)
+        },
+    );
+    builtin_funcs.insert(
+        "dict".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if !args.len().is_multiple_of(2) {
+                return Err(LithpError::arity("an even number", args.len(), span));
+            }
+            let mut entries = Dict::new();
+            for pair in args.chunks(2) {
+                insert_entry(&mut entries, &pair[0], pair[1].value.clone())?;
+            }
+            Ok(Value::Dict(entries))
+        },
+    );
+    builtin_funcs.insert(
+        "get".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 && args.len() != 3 {
+                return Err(LithpError::arity("2 or 3", args.len(), span));
+            }
+            let entries = dict_arg(&args[0])?;
+            match entries.get(&key_arg(&args[1])?) {
+                Some((_, value)) => Ok(value.clone()),
+                // The optional third argument is a default for missing keys
+                None if args.len() == 3 => Ok(args[2].value.clone()),
+                None => Err(LithpError::Runtime {
+                    message: format!("Key {} not found", to_string(&args[1].value)),
+                    span: args[1].span,
+                }),
+            }
+        },
+    );
+    builtin_funcs.insert(
+        "put".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 3 {
+                return Err(LithpError::arity(3, args.len(), span));
+            }
+            let mut entries = dict_arg(&args[0])?.clone();
+            insert_entry(&mut entries, &args[1], args[2].value.clone())?;
+            Ok(Value::Dict(entries))
+        },
+    );
+    builtin_funcs.insert(
+        "remove".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let entries = dict_arg(&args[0])?;
+            Ok(Value::Dict(entries.without(&key_arg(&args[1])?)))
+        },
+    );
+    builtin_funcs.insert(
+        "has".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let entries = dict_arg(&args[0])?;
+            Ok(Value::Boolean(entries.contains_key(&key_arg(&args[1])?)))
+        },
+    );
+    builtin_funcs.insert(
+        "keys".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let entries = sorted_entries(dict_arg(&args[0])?);
+            let keys = entries.iter().map(|(key, _)| key.clone()).collect();
+            Ok(Value::List(keys))
+        },
+    );
+    builtin_funcs.insert(
+        "values".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let entries = sorted_entries(dict_arg(&args[0])?);
+            let values = entries.iter().map(|(_, value)| value.clone()).collect();
+            Ok(Value::List(values))
+        },
+    );
+    builtin_funcs.insert(
+        "entries".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            let entries = sorted_entries(dict_arg(&args[0])?);
+            let pairs = entries
+                .iter()
+                .map(|(key, value)| Value::List(List::from(vec![key.clone(), value.clone()])))
+                .collect();
+            Ok(Value::List(pairs))
+        },
+    );
+    builtin_funcs.insert(
+        "concat".to_owned(),
+        |_env: &mut Environment, args: &[Arg], _span: Span| -> Result<Value, LithpError> {
+            let strings = args.iter().map(|arg| to_string(&arg.value));
+            Ok(Value::String(strings.collect()))
+        },
+    );
+    builtin_funcs.insert(
+        "substr".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 && args.len() != 3 {
+                return Err(LithpError::arity("2 or 3", args.len(), span));
+            }
+            let string = string_arg(&args[0])?;
+            let len = string.chars().count();
+            let start = index_arg(&args[1])?;
+            // The end is exclusive and defaults to the end of the string
+            let end = match args.get(2) {
+                Some(arg) => index_arg(arg)?,
+                None => len,
             };
-            let mut largest_value = Expr::IntLiteral(1);
-            let mut difference = match eval_non_literal(env, &args[0])? {
-                Expr::DoubleLiteral(double) => double,
-                Expr::LongLiteral(long) => long as f64,
-                Expr::IntLiteral(int) => int as f64,
-                _ => return Err("Not a numeric value".to_owned()),
-            };
-            for arg in &args[1..] {
-                match eval_non_literal(env, &arg) {
-                    Ok(Expr::IntLiteral(int)) => {
-                        change_if_larger(&mut largest_value, &Expr::IntLiteral(1));
-                        difference -= int as f64;
-                    }
-                    Ok(Expr::LongLiteral(long)) => {
-                        change_if_larger(&mut largest_value, &Expr::LongLiteral(1));
-                        difference -= long as f64;
-                    }
-                    Ok(Expr::DoubleLiteral(double)) => {
-                        change_if_larger(&mut largest_value, &Expr::DoubleLiteral(1.0));
-                        difference -= double;
-                    }
-                    Ok(_) => return Err("Not a numeric value".to_owned()),
-                    Err(err) => return Err(err),
-                }
+            if start > end || end > len {
+                return Err(LithpError::Runtime {
+                    message: format!(
+                        "Range {} to {} is out of bounds for length {}",
+                        start, end, len
+                    ),
+                    span,
+                });
+            }
+            let substring = string.chars().skip(start).take(end - start).collect();
+            Ok(Value::String(substring))
+        },
+    );
+    builtin_funcs.insert(
+        "indexOf".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let string = string_arg(&args[0])?;
+            let pattern = string_arg(&args[1])?;
+            // Positions are in characters, like those taken by `substr`
+            Ok(match string.find(pattern) {
+                Some(byte_idx) => number::from_bigint(string[..byte_idx].chars().count().into()),
+                None => Value::Nil,
+            })
+        },
+    );
+    builtin_funcs.insert(
+        "split".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let string = string_arg(&args[0])?;
+            let separator = string_arg(&args[1])?;
+            if separator.is_empty() {
+                return Err(LithpError::Runtime {
+                    message: "Cannot split on an empty separator".to_owned(),
+                    span: args[1].span,
+                });
+            }
+            let parts = string
+                .split(separator)
+                .map(|part| Value::String(part.to_owned()))
+                .collect();
+            Ok(Value::List(parts))
+        },
+    );
+    builtin_funcs.insert(
+        "join".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let items = list_arg(&args[0])?;
+            let separator = string_arg(&args[1])?;
+            let strings = items.iter().map(to_string).collect::<Vec<_>>();
+            Ok(Value::String(strings.join(separator)))
+        },
+    );
+    builtin_funcs.insert(
+        "upper".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            Ok(Value::String(string_arg(&args[0])?.to_uppercase()))
+        },
+    );
+    builtin_funcs.insert(
+        "lower".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            Ok(Value::String(string_arg(&args[0])?.to_lowercase()))
+        },
+    );
+    builtin_funcs.insert(
+        "trim".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 1 {
+                return Err(LithpError::arity(1, args.len(), span));
+            }
+            Ok(Value::String(string_arg(&args[0])?.trim().to_owned()))
+        },
+    );
+    builtin_funcs.insert(
+        "replace".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 3 {
+                return Err(LithpError::arity(3, args.len(), span));
+            }
+            let string = string_arg(&args[0])?;
+            let from = string_arg(&args[1])?;
+            let to = string_arg(&args[2])?;
+            if from.is_empty() {
+                return Err(LithpError::Runtime {
+                    message: "Cannot replace an empty string".to_owned(),
+                    span: args[1].span,
+                });
+            }
+            Ok(Value::String(string.replace(from, to)))
+        },
+    );
+    builtin_funcs.insert(
+        "startsWith".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
             }
-            Ok(match largest_value {
-                Expr::IntLiteral(_) => Expr::IntLiteral(difference as i32),
-                Expr::LongLiteral(_) => Expr::LongLiteral(difference as i64),
-                Expr::DoubleLiteral(_) => Expr::DoubleLiteral(difference),
-                _ => return Err("Not a numeric value".to_owned()),
-            })
+            let string = string_arg(&args[0])?;
+            let prefix = string_arg(&args[1])?;
+            Ok(Value::Boolean(string.starts_with(prefix)))
         },
     );
     builtin_funcs.insert(
-        "==".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            for arg_idx in 0..args.len() - 1 {
-                let eq_left = eval_non_literal(env, &args[arg_idx])?;
-                let eq_right = eval_non_literal(env, &args[arg_idx + 1])?;
-                match eq_left {
-                    Expr::IntLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right != eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right != eq_left as i64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right != eq_left as f64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
-                    }
-                    Expr::LongLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right as i64 != eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right != eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right != eq_left as f64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
-                    }
-                    Expr::DoubleLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right as f64 != eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right as f64 != eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right != eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
-                    }
-                    Expr::StringLiteral(eq_left) => if let Expr::StringLiteral(eq_right) = eq_right {
-                        if eq_left != eq_right {
-                            return Ok(Expr::BooleanLiteral(false));
-                        }
-                    } else {
-                        return Err("Arguments are not the same type".to_owned());
-                    }
-                    // Hopefully not possible
-                    _ => (),
-                }
+        "endsWith".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
             }
-            Ok(Expr::BooleanLiteral(true))
-        }
+            let string = string_arg(&args[0])?;
+            let suffix = string_arg(&args[1])?;
+            Ok(Value::Boolean(string.ends_with(suffix)))
+        },
     );
     builtin_funcs.insert(
-        ">".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
-            for arg_idx in 0..args.len() - 1 {
-                let eq_left = eval_non_literal(env, &args[arg_idx])?;
-                let eq_right = eval_non_literal(env, &args[arg_idx + 1])?;
-                match eq_left {
-                    Expr::IntLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right >= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right >= eq_left as i64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right >= eq_left as f64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
+        "format".to_owned(),
+        |_env: &mut Environment, args: &[Arg], span: Span| -> Result<Value, LithpError> {
+            if args.is_empty() {
+                return Err(LithpError::arity("1 or more", args.len(), span));
+            }
+            let template = string_arg(&args[0])?;
+            // Each `{}` takes the next value, and `{{` and `}}` stand for literal braces
+            let mut formatted = String::new();
+            let mut values = args[1..].iter().map(|arg| &arg.value);
+            let mut placeholders = 0;
+            let mut chars = template.chars().peekable();
+            while let Some(character) = chars.next() {
+                match (character, chars.peek()) {
+                    ('{', Some('}')) => {
+                        chars.next();
+                        placeholders += 1;
+                        if let Some(value) = values.next() {
+                            formatted += &to_string(value);
                         }
                     }
-                    Expr::LongLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right as i64 >= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right >= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right >= eq_left as f64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
+                    ('{', Some('{')) | ('}', Some('}')) => {
+                        chars.next();
+                        formatted.push(character);
                     }
-                    Expr::DoubleLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right as f64 >= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right as f64 >= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right >= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
+                    ('{', _) | ('}', _) => {
+                        return Err(LithpError::Runtime {
+                            message: format!("Unmatched '{}' in format string", character),
+                            span: args[0].span,
+                        })
                     }
-                    _ => return Err("Cannot compare these types".to_owned()),
+                    _ => formatted.push(character),
                 }
             }
-            Ok(Expr::BooleanLiteral(true))
-        }
+            if placeholders != args.len() - 1 {
+                return Err(LithpError::Runtime {
+                    message: format!(
+                        "Format string has {} placeholders but got {} values",
+                        placeholders,
+                        args.len() - 1
+                    ),
+                    span,
+                });
+            }
+            Ok(Value::String(formatted))
+        },
     );
-    builtin_funcs.insert(
-        "<".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
+    builtin_funcs
+}
+
+fn special_forms() -> HashMap<String, SpecialFormFn> {
+    let mut special_forms: HashMap<String, SpecialFormFn> = HashMap::new();
+    special_forms.insert(
+        "=".to_owned(),
+        |env: &mut Environment, args: &[Expr], span: Span| -> Result<Form, LithpError> {
+            if args.len() != 2 {
+                return Err(LithpError::arity(2, args.len(), span));
+            }
+            let name = match &args[0].kind {
+                ExprKind::Identifier(name) => name.clone(),
+                _ => return Err(LithpError::type_mismatch("Expected identifier for argument 0, did not get identifier", args[0].span)),
+            };
+            let value = eval_non_literal(env, &args[1])?;
+            Scope::bind(&env.scope, name, value);
+            Ok(Form::Value(Value::Nil))
+        },
+    );
+    special_forms.insert(
+        "func".to_owned(),
+        |env: &mut Environment, args: &[Expr], span: Span| -> Result<Form, LithpError> {
+            if args.is_empty() {
+                return Err(LithpError::arity("1 or more", args.len(), span));
+            }
+            let mut params = Vec::new();
             for arg_idx in 0..args.len() - 1 {
-                let eq_left = eval_non_literal(env, &args[arg_idx])?;
-                let eq_right = eval_non_literal(env, &args[arg_idx + 1])?;
-                match eq_left {
-                    Expr::IntLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if eq_right <= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right <= eq_left as i64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right <= eq_left as f64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
-                    }
-                    Expr::LongLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if (eq_right as i64) <= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if eq_right <= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right <= eq_left as f64 {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
-                    }
-                    Expr::DoubleLiteral(eq_left) => {
-                        match eq_right {
-                            Expr::IntLiteral(eq_right) => if (eq_right as f64) <= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::LongLiteral(eq_right) => if (eq_right as f64) <= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            Expr::DoubleLiteral(eq_right) => if eq_right <= eq_left {
-                                return Ok(Expr::BooleanLiteral(false));
-                            }
-                            _ => return Err("Arguments are not the same type".to_owned()),
-                        }
-                    }
-                    Expr::StringLiteral(eq_left) => if let Expr::StringLiteral(eq_right) = eq_right {
-                        if eq_left != eq_right {
-                            return Ok(Expr::BooleanLiteral(false));
-                        }
-                    } else {
-                        return Err("Arguments are not the same type".to_owned());
+                match &args[arg_idx].kind {
+                    ExprKind::Identifier(name) => params.push(name.clone()),
+                    _ => {
+                        return Err(LithpError::type_mismatch(
+                            format!(
+                                "Expected identifier for argument {}, did not get identifier",
+                                arg_idx
+                            ),
+                            args[arg_idx].span,
+                        ))
                     }
-                    _ => return Err("Cannot compare these types".to_owned()),
                 }
             }
-            Ok(Expr::BooleanLiteral(true))
-        }
+            let body = args.last().unwrap();
+            let closure = Closure {
+                code: Rc::new(Code {
+                    params,
+                    body: body.clone(),
+                }),
+                scope: Captured::Strong(env.scope.clone()),
+            };
+            Ok(Form::Value(Value::Closure(Rc::new(closure))))
+        },
     );
-    builtin_funcs.insert(
+    special_forms.insert(
         "ifElse".to_owned(),
-        |env: &mut Environment, args: &Vec<Expr>| -> Result<Expr, String> {
+        |env: &mut Environment, args: &[Expr], span: Span| -> Result<Form, LithpError> {
             if args.len() != 3 {
-                return Err(format!(
-                    "Wrong number of arguments; expected 3, got {}",
-                    args.len()
-                ));
+                return Err(LithpError::arity(3, args.len(), span));
             }
             let exec_expr = eval_non_literal(env, &args[0])?;
             match exec_expr {
-                Expr::BooleanLiteral(boolean) => {
+                Value::Boolean(boolean) => {
                     if boolean {
-                        Ok(args[1].clone())
+                        Ok(Form::Branch(1))
                     } else {
-                        Ok(args[2].clone())
+                        Ok(Form::Branch(2))
                     }
                 }
-                _ => return Err("Expected boolean for argument 0, did not get boolean".to_owned()),
+                _ => Err(LithpError::type_mismatch("Expected boolean for argument 0, did not get boolean", args[0].span)),
             }
         },
     );
-    let mut env = Environment {
-        vars: HashMap::new(),
-        builtin_funcs,
-    };
-    for expr in exprs {
-        match expr {
-            Expr::FuncCall(name, args) => {
-                let clone = env.clone();
-                if let Some(function) = clone.builtin_funcs.get(&name) {
-                    function(&mut env, &args)?
-                } else {
-                    return Err("Undeclared function".to_owned());
+    special_forms.insert(
+        "and".to_owned(),
+        |env: &mut Environment, args: &[Expr], _span: Span| -> Result<Form, LithpError> {
+            // Stops at the first false argument without evaluating the rest
+            for arg in args {
+                if !eval_bool(env, arg)? {
+                    return Ok(Form::Value(Value::Boolean(false)));
+                }
+            }
+            Ok(Form::Value(Value::Boolean(true)))
+        },
+    );
+    special_forms.insert(
+        "or".to_owned(),
+        |env: &mut Environment, args: &[Expr], _span: Span| -> Result<Form, LithpError> {
+            // Stops at the first true argument without evaluating the rest
+            for arg in args {
+                if eval_bool(env, arg)? {
+                    return Ok(Form::Value(Value::Boolean(true)));
+                }
+            }
+            Ok(Form::Value(Value::Boolean(false)))
+        },
+    );
+    special_forms.insert(
+        "cond".to_owned(),
+        |env: &mut Environment, args: &[Expr], span: Span| -> Result<Form, LithpError> {
+            // Pairs of tests and branches, followed by the branch to take if no test is true
+            if args.len().is_multiple_of(2) {
+                return Err(LithpError::arity("an odd number", args.len(), span));
+            }
+            for pair_idx in (0..args.len() - 1).step_by(2) {
+                if eval_bool(env, &args[pair_idx])? {
+                    return Ok(Form::Branch(pair_idx + 1));
+                }
+            }
+            Ok(Form::Branch(args.len() - 1))
+        },
+    );
+    special_forms.insert(
+        "switch".to_owned(),
+        |env: &mut Environment, args: &[Expr], span: Span| -> Result<Form, LithpError> {
+            // A value, then pairs of cases and branches, then the branch to take if no case is `==`
+            // to the value
+            if args.len() < 2 || !args.len().is_multiple_of(2) {
+                return Err(LithpError::arity("an even number of at least 2", args.len(), span));
+            }
+            let value = eval_non_literal(env, &args[0])?;
+            for pair_idx in (1..args.len() - 1).step_by(2) {
+                if equal(&value, &eval_non_literal(env, &args[pair_idx])?) {
+                    return Ok(Form::Branch(pair_idx + 1));
                 }
             }
-            _ => return Err("Unsupported operation".to_owned()),
+            Ok(Form::Branch(args.len() - 1))
+        },
+    );
+    special_forms
+}
+
+fn eval_non_literal(env: &mut Environment, expr: &Expr) -> Result<Value, LithpError> {
+    match eval_tail(env, expr)? {
+        Tail::Value(value) => Ok(value),
+        Tail::Call(closure, args) => call_closure(env, closure, args),
+    }
+}
+
+fn eval_bool(env: &mut Environment, expr: &Expr) -> Result<bool, LithpError> {
+    match eval_non_literal(env, expr)? {
+        Value::Boolean(boolean) => Ok(boolean),
+        _ => Err(LithpError::type_mismatch("Not a boolean value", expr.span)),
+    }
+}
+
+/// Checks that each adjacent pair of `args` is ordered as `accept` requires. Values that have no
+/// order are an error.
+fn compare_chain(
+    args: &[Arg],
+    span: Span,
+    accept: fn(Ordering) -> bool,
+) -> Result<Value, LithpError> {
+    if args.is_empty() {
+        return Err(LithpError::arity("1 or more", args.len(), span));
+    }
+    for pair in args.windows(2) {
+        let (left, right) = (&pair[0].value, &pair[1].value);
+        let ordering = match compare(left, right) {
+            Some(ordering) if is_ordered(left) && is_ordered(right) => ordering,
+            _ if number::is_number(left) && number::is_number(right) => {
+                return Err(LithpError::type_mismatch("Cannot order NaN", pair[1].span))
+            }
+            _ => {
+                return Err(LithpError::type_mismatch(
+                    format!("Cannot order {} and {}", type_of(left), type_of(right)),
+                    pair[1].span,
+                ))
+            }
         };
+        if !accept(ordering) {
+            return Ok(Value::Boolean(false));
+        }
+    }
+    Ok(Value::Boolean(true))
+}
+
+/// Tests the single argument of a type predicate such as `isInt`.
+fn is_type(args: &[Arg], span: Span, predicate: fn(&Value) -> bool) -> Result<Value, LithpError> {
+    if args.len() != 1 {
+        return Err(LithpError::arity(1, args.len(), span));
+    }
+    Ok(Value::Boolean(predicate(&args[0].value)))
+}
+
+fn number_arg(arg: &Arg) -> Result<Value, LithpError> {
+    if number::is_number(&arg.value) {
+        Ok(arg.value.clone())
+    } else {
+        Err(LithpError::type_mismatch("Not a numeric value", arg.span))
+    }
+}
+
+fn bool_arg(arg: &Arg) -> Result<bool, LithpError> {
+    match arg.value {
+        Value::Boolean(boolean) => Ok(boolean),
+        _ => Err(LithpError::type_mismatch("Not a boolean value", arg.span)),
+    }
+}
+
+/// Reads `arg` as a whole number, truncating fractional numbers towards zero and parsing strings.
+fn integer_arg(arg: &Arg) -> Result<BigInt, LithpError> {
+    let value = &arg.value;
+    let integer = match value {
+        Value::String(string) if is_integer(string.trim()) => string.trim().parse::<BigInt>().ok(),
+        Value::String(_) => None,
+        _ if number::is_number(value) => number::to_bigint(value),
+        _ => {
+            return Err(LithpError::type_mismatch(
+                "Expected a number or string",
+                arg.span,
+            ))
+        }
+    };
+    integer.ok_or_else(|| LithpError::Runtime {
+        message: format!("Cannot convert {} to an integer", to_string(value)),
+        span: arg.span,
+    })
+}
+
+fn string_arg(arg: &Arg) -> Result<&str, LithpError> {
+    match &arg.value {
+        Value::String(string) => Ok(string),
+        _ => Err(LithpError::type_mismatch("Not a string", arg.span)),
+    }
+}
+
+fn list_arg(arg: &Arg) -> Result<&List, LithpError> {
+    match &arg.value {
+        Value::List(items) => Ok(items),
+        _ => Err(LithpError::type_mismatch("Not a list", arg.span)),
+    }
+}
+
+fn dict_arg(arg: &Arg) -> Result<&Dict, LithpError> {
+    match &arg.value {
+        Value::Dict(entries) => Ok(entries),
+        _ => Err(LithpError::type_mismatch("Not a dict", arg.span)),
+    }
+}
+
+/// Reads `arg` as a dict key, which must be a string, boolean or number other than NaN.
+fn key_arg(arg: &Arg) -> Result<Key, LithpError> {
+    match Key::new(&arg.value) {
+        Some(key) => Ok(key),
+        None if number::is_number(&arg.value) => {
+            Err(LithpError::type_mismatch("Dict keys cannot be NaN", arg.span))
+        }
+        None => Err(LithpError::type_mismatch(
+            "Dict keys must be strings, numbers or booleans",
+            arg.span,
+        )),
+    }
+}
+
+/// Sets the key `arg` to `value` in `entries`, keeping the key that is already there if there is
+/// one equal to it.
+fn insert_entry(entries: &mut Dict, arg: &Arg, value: Value) -> Result<(), LithpError> {
+    let key = key_arg(arg)?;
+    match entries.get_mut(&key) {
+        Some(entry) => entry.1 = value,
+        None => {
+            entries.insert(key, (arg.value.clone(), value));
+        }
     }
     Ok(())
 }
 
-fn eval_non_literal(env: &mut Environment, expr: &Expr) -> Result<Expr, String> {
-    match expr {
-        Expr::Identifier(name) => {
-            let env_clone = env.clone();
-            if let Some(var) = env_clone.vars.get(name) {
-                eval_non_literal(env, var)
+/// The entries of `entries` in the order of their keys, so that a dict is always listed the same
+/// way.
+fn sorted_entries(entries: &Dict) -> Vec<&(Value, Value)> {
+    let mut sorted = entries.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|(key, _)| *key);
+    sorted.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Reads `arg` as a position in a sequence, which must be a non-negative integer.
+fn index_arg(arg: &Arg) -> Result<usize, LithpError> {
+    let idx = match arg.value {
+        Value::Int(int) => usize::try_from(int).ok(),
+        Value::Long(long) => usize::try_from(long).ok(),
+        _ => None,
+    };
+    idx.ok_or_else(|| LithpError::type_mismatch("Expected a non-negative integer index", arg.span))
+}
+
+/// Compares two values. Numbers compare by value whatever their type, strings lexicographically
+/// and lists element by element. Any other values, and values of different types, are only ever
+/// equal or unequal, so `None` means that they are unequal and have no order.
+pub fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (left, right) if number::is_number(left) && number::is_number(right) => {
+            number::compare(left.clone(), right.clone())
+        }
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::List(left), Value::List(right)) => {
+            for (left, right) in left.iter().zip(right.iter()) {
+                match compare(left, right)? {
+                    Ordering::Equal => (),
+                    ordering => return Some(ordering),
+                }
+            }
+            Some(left.len().cmp(&right.len()))
+        }
+        (Value::Dict(left), Value::Dict(right)) => {
+            let same = left.len() == right.len()
+                && left.iter().all(|(key, (_, value))| match right.get(key) {
+                    Some((_, other)) => equal(value, other),
+                    None => false,
+                });
+            if same {
+                Some(Ordering::Equal)
             } else {
-                Err("Undeclared variable".to_owned())
+                None
             }
         }
-        Expr::FuncCall(name, args) => {
-            if *name == "func".to_owned() {
-                return Ok(expr.clone());
-            }
-            let env_clone = env.clone();
-            if let Some(func) = env_clone.vars.get(name) {
-                let mut env_shadow = env.clone();
-                let func_expr = match func {
-                    Expr::FuncCall(name, func_args) => {
-                        if *name != "func".to_owned() {
-                            return Err(format!("{} is not a function", name));
-                        }
-                        if func_args.len() - 1 != args.len() {
-                            return Err(format!(
-                                "Wrong number of arguments; expected {}, got {}",
-                                func_args.len() - 1,
-                                args.len()
-                            ));
-                        }
-                        for arg_idx in 0..args.len() {
-                            match &func_args[arg_idx] {
-                                Expr::Identifier(name) => {
-                                    env_shadow.vars.insert(name.clone(), eval_non_literal(env, &args[arg_idx])?);
-                                }
-                                _ => (),
-                            }
-                        }
-                        func_args.last().unwrap()
-                    }
-                    _ => return Err("Not a function".to_owned()),
-                };
-                eval_non_literal(&mut env_shadow, func_expr)
-            } else if let Some(func) = env_clone.builtin_funcs.get(name) {
-                let expr = func(env, args)?;
-                eval_non_literal(env, &expr)
+        // Booleans, functions and nil are equal only to themselves
+        (left, right) if left == right => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+pub fn equal(left: &Value, right: &Value) -> bool {
+    compare(left, right) == Some(Ordering::Equal)
+}
+
+/// Whether values of the type of `value` can be ordered, rather than only tested for equality.
+fn is_ordered(value: &Value) -> bool {
+    match value {
+        Value::String(_) => true,
+        Value::List(items) => items.iter().all(is_ordered),
+        _ => number::is_number(value),
+    }
+}
+
+/// Calls the function `func` with arguments that have already been evaluated.
+fn call_value(
+    env: &mut Environment,
+    func: &Value,
+    args: Vec<Value>,
+    span: Span,
+) -> Result<Value, LithpError> {
+    let tail = match func {
+        Value::Closure(closure) => {
+            if closure.code.params.len() != args.len() {
+                return Err(LithpError::arity(closure.code.params.len(), args.len(), span));
+            }
+            Tail::Call(closure.clone(), args)
+        }
+        Value::Builtin(name) if env.builtins.special_forms.contains_key(name) => {
+            return Err(special_form_value(name, span))
+        }
+        Value::Builtin(name) => {
+            let args = args
+                .into_iter()
+                .map(|value| Arg { value, span })
+                .collect::<Vec<_>>();
+            Tail::Value(call_func(env, name, &args, span)?)
+        }
+        _ => {
+            return Err(LithpError::NotAFunction {
+                name: to_string(func),
+                span,
+            })
+        }
+    };
+    match tail {
+        Tail::Value(value) => Ok(value),
+        Tail::Call(closure, args) => call_closure(env, closure, args),
+    }
+}
+
+/// The error for calling a special form through a value, where its arguments would already have
+/// been evaluated.
+fn special_form_value(name: &str, span: Span) -> LithpError {
+    LithpError::type_mismatch(
+        format!("{} is a special form, so it can only be called by name", name),
+        span,
+    )
+}
+
+/// The result of evaluating an expression in tail position: either a value, or a call to a
+/// closure that is left for the caller to make so that it doesn't grow the stack.
+enum Tail {
+    Value(Value),
+    Call(Rc<Closure>, Vec<Value>),
+}
+
+fn eval_tail(env: &mut Environment, expr: &Expr) -> Result<Tail, LithpError> {
+    let span = expr.span;
+    match &expr.kind {
+        ExprKind::Identifier(name) => {
+            let var = env.scope.borrow().get(name);
+            if let Some(var) = var {
+                Ok(Tail::Value(var))
+            } else if env.builtins.contains(name) {
+                Ok(Tail::Value(Value::Builtin(name.clone())))
             } else {
-                Err("Undeclared function".to_owned())
+                Err(LithpError::UndeclaredVariable {
+                    name: name.clone(),
+                    span,
+                })
+            }
+        }
+        ExprKind::FuncCall(callee, args) => {
+            let func = match &callee.kind {
+                ExprKind::Identifier(name) => {
+                    let var = env.scope.borrow().get(name);
+                    match var {
+                        Some(var) => var,
+                        // Builtins are called by name directly, rather than through a value
+                        None => return call_builtin(env, name, args, span),
+                    }
+                }
+                _ => eval_non_literal(env, callee)?,
+            };
+            match func {
+                Value::Closure(closure) => {
+                    if closure.code.params.len() != args.len() {
+                        return Err(LithpError::arity(
+                            closure.code.params.len(),
+                            args.len(),
+                            span,
+                        ));
+                    }
+                    let mut values = Vec::new();
+                    for arg in args {
+                        values.push(eval_non_literal(env, arg)?);
+                    }
+                    Ok(Tail::Call(closure, values))
+                }
+                Value::Builtin(name) if env.builtins.special_forms.contains_key(&name) => {
+                    Err(special_form_value(&name, span))
+                }
+                Value::Builtin(name) => call_builtin(env, &name, args, span),
+                func => Err(LithpError::NotAFunction {
+                    name: match &callee.kind {
+                        ExprKind::Identifier(name) => name.clone(),
+                        _ => to_string(&func),
+                    },
+                    span,
+                }),
+            }
+        }
+        ExprKind::StringLiteral(string) => Ok(Tail::Value(Value::String(string.clone()))),
+        ExprKind::IntLiteral(int) => Ok(Tail::Value(Value::Int(*int))),
+        ExprKind::LongLiteral(long) => Ok(Tail::Value(Value::Long(*long))),
+        ExprKind::BigIntLiteral(big) => Ok(Tail::Value(Value::BigInt(big.clone()))),
+        ExprKind::DoubleLiteral(double) => Ok(Tail::Value(Value::Double(*double))),
+        ExprKind::BooleanLiteral(boolean) => Ok(Tail::Value(Value::Boolean(*boolean))),
+        ExprKind::NilLiteral => Ok(Tail::Value(Value::Nil)),
+    }
+}
+
+fn call_builtin(
+    env: &mut Environment,
+    name: &str,
+    args: &[Expr],
+    span: Span,
+) -> Result<Tail, LithpError> {
+    if let Some(form) = env.builtins.special_forms.get(name).copied() {
+        return match form(env, args, span)? {
+            Form::Value(value) => Ok(Tail::Value(value)),
+            Form::Branch(branch) => eval_tail(env, &args[branch]),
+        };
+    }
+    if !env.builtins.contains(name) {
+        return Err(LithpError::UndeclaredFunction {
+            name: name.to_owned(),
+            span,
+        });
+    }
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        values.push(Arg {
+            value: eval_non_literal(env, arg)?,
+            span: arg.span,
+        });
+    }
+    Ok(Tail::Value(call_func(env, name, &values, span)?))
+}
+
+/// Calls the builtin or host function `name` with arguments that have already been evaluated.
+fn call_func(
+    env: &mut Environment,
+    name: &str,
+    args: &[Arg],
+    span: Span,
+) -> Result<Value, LithpError> {
+    if let Some(func) = env.builtins.funcs.get(name).copied() {
+        func(env, args, span)
+    } else if let Some(func) = env.builtins.host_fns.get(name).cloned() {
+        let values = args.iter().map(|arg| arg.value.clone()).collect::<Vec<_>>();
+        // Host code has no spans of its own, so its errors are placed at the call
+        func(&values).map_err(|err| err.or_span(span))
+    } else {
+        Err(LithpError::UndeclaredFunction {
+            name: name.to_owned(),
+            span,
+        })
+    }
+}
+
+fn call_closure(
+    env: &Environment,
+    mut closure: Rc<Closure>,
+    mut args: Vec<Value>,
+) -> Result<Value, LithpError> {
+    loop {
+        // Arguments are bound in a new scope whose parent is the one the function was defined in
+        let vars = closure.code.params.iter().cloned().zip(args).collect();
+        let mut env_shadow = Environment {
+            scope: Rc::new(RefCell::new(Scope {
+                vars,
+                parent: Some(closure.scope()),
+            })),
+            builtins: env.builtins.clone(),
+        };
+        match eval_tail(&mut env_shadow, &closure.code.body)? {
+            Tail::Value(value) => return Ok(value),
+            Tail::Call(next_closure, next_args) => {
+                closure = next_closure;
+                args = next_args;
             }
         }
-        _ => Ok(expr.clone()),
     }
 }
 
-fn to_string(expr: &Expr) -> Result<String, String> {
-    match expr {
-        Expr::StringLiteral(string) => Ok(string.clone()),
-        Expr::IntLiteral(int) => Ok(int.to_string()),
-        Expr::LongLiteral(long) => Ok(long.to_string()),
-        Expr::DoubleLiteral(double) => Ok(double.to_string()),
-        Expr::BooleanLiteral(boolean) => Ok(boolean.to_string()),
-        _ => Err("Cannot convert identifier or function to string".to_owned()),
+/// The name of the type of `value`, as given by `typeOf`.
+pub fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Int(_) => "int",
+        Value::Long(_) => "long",
+        Value::BigInt(_) => "bigint",
+        Value::Rational(_) => "rational",
+        Value::Double(_) => "double",
+        Value::String(_) => "string",
+        Value::Boolean(_) => "bool",
+        Value::Closure(_) => "func",
+        Value::Builtin(_) => "builtin",
+        Value::List(_) => "list",
+        Value::Dict(_) => "dict",
+        Value::Nil => "nil",
+    }
+}
+
+pub fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Int(int) => int.to_string(),
+        Value::Long(long) => long.to_string(),
+        Value::BigInt(big) => big.to_string(),
+        Value::Rational(ratio) => ratio.to_string(),
+        Value::Double(double) => double.to_string(),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::Closure(_) => "<func>".to_owned(),
+        Value::Builtin(name) => format!("<builtin {}>", name),
+        Value::List(items) => format!(
+            "[{}]",
+            items.iter().map(to_string).collect::<Vec<_>>().join(" ")
+        ),
+        Value::Dict(entries) => format!(
+            "{{{}}}",
+            sorted_entries(entries)
+                .iter()
+                .map(|(key, value)| format!("{} {}", to_string(key), to_string(value)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        Value::Nil => "nil".to_owned(),
+    }
+}
+
+/// A builtin function, which is passed its arguments already evaluated.
+type BuiltinFn = fn(&mut Environment, &[Arg], Span) -> Result<Value, LithpError>;
+/// A function registered by the program embedding the interpreter, which is passed its arguments
+/// already evaluated. Unlike a builtin, it can capture state.
+type HostFn = Rc<dyn Fn(&[Value]) -> Result<Value, LithpError>>;
+/// A form that is passed its arguments unevaluated, e.g. `ifElse`, which only evaluates one of
+/// its branches.
+type SpecialFormFn = fn(&mut Environment, &[Expr], Span) -> Result<Form, LithpError>;
+
+/// An evaluated argument to a builtin function, with the span of the expression it came from.
+struct Arg {
+    value: Value,
+    span: Span,
+}
+
+/// What a special form evaluates to: either a value, or which of its arguments to evaluate next,
+/// in tail position.
+enum Form {
+    Value(Value),
+    Branch(usize),
+}
+
+/// Cloning an environment is cheap, since both its scope and builtins are reference-counted.
+#[derive(Clone)]
+struct Environment {
+    scope: Rc<RefCell<Scope>>,
+    builtins: Rc<Builtins>,
+}
+
+impl Environment {
+    fn new() -> Environment {
+        Environment {
+            scope: Rc::new(RefCell::new(Scope {
+                vars: HashMap::new(),
+                parent: None,
+            })),
+            builtins: Rc::new(Builtins {
+                funcs: builtin_funcs(),
+                special_forms: special_forms(),
+                host_fns: HashMap::new(),
+            }),
+        }
     }
 }
 
+/// The builtin functions, special forms and host functions, shared by every environment of an
+/// interpreter.
 #[derive(Clone)]
-pub struct Environment {
-    vars: HashMap<String, Expr>,
-    builtin_funcs: HashMap<String, fn(&mut Environment, &Vec<Expr>) -> Result<Expr, String>>,
+struct Builtins {
+    funcs: HashMap<String, BuiltinFn>,
+    special_forms: HashMap<String, SpecialFormFn>,
+    host_fns: HashMap<String, HostFn>,
+}
+
+impl Builtins {
+    fn contains(&self, name: &str) -> bool {
+        self.funcs.contains_key(name)
+            || self.special_forms.contains_key(name)
+            || self.host_fns.contains_key(name)
+    }
+}
+
+/// The variables bound at the top level or by one function call, falling back to the scope it
+/// was created in.
+struct Scope {
+    vars: HashMap<String, Value>,
+    parent: Option<Rc<RefCell<Scope>>>,
+}
+
+impl Scope {
+    fn get(&self, name: &str) -> Option<Value> {
+        match self.vars.get(name) {
+            Some(Value::Closure(closure)) => Some(Value::Closure(closure.upgrade())),
+            Some(var) => Some(var.clone()),
+            None => self.parent.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Binds `name` in `scope`. A function defined in `scope` only keeps a weak reference to it
+    /// while it is stored there, since otherwise the two would keep each other alive.
+    fn bind(scope: &Rc<RefCell<Scope>>, name: String, value: Value) {
+        let value = match value {
+            Value::Closure(closure) => Value::Closure(closure.downgrade(scope)),
+            value => value,
+        };
+        scope.borrow_mut().vars.insert(name, value);
+    }
+}
+
+/// A function value, which captures the scope it was defined in.
+pub struct Closure {
+    code: Rc<Code>,
+    scope: Captured,
+}
+
+/// The parameters and body of a function, shared by every copy of it.
+struct Code {
+    params: Vec<String>,
+    body: Expr,
+}
+
+/// How a closure holds on to the scope it was defined in. Only a closure stored in that same
+/// scope holds it weakly, and it is upgraded again whenever it is read from there.
+enum Captured {
+    Strong(Rc<RefCell<Scope>>),
+    Weak(Weak<RefCell<Scope>>),
+}
+
+impl Closure {
+    fn scope(&self) -> Rc<RefCell<Scope>> {
+        match &self.scope {
+            Captured::Strong(scope) => scope.clone(),
+            Captured::Weak(scope) => scope
+                .upgrade()
+                .expect("A closure outlived the scope that it is stored in"),
+        }
+    }
+
+    fn scope_ptr(&self) -> *const RefCell<Scope> {
+        match &self.scope {
+            Captured::Strong(scope) => Rc::as_ptr(scope),
+            Captured::Weak(scope) => scope.as_ptr(),
+        }
+    }
+
+    /// The copy of `self` to store in `scope`.
+    fn downgrade(self: Rc<Closure>, scope: &Rc<RefCell<Scope>>) -> Rc<Closure> {
+        match &self.scope {
+            Captured::Strong(captured) if Rc::ptr_eq(captured, scope) => Rc::new(Closure {
+                code: self.code.clone(),
+                scope: Captured::Weak(Rc::downgrade(scope)),
+            }),
+            _ => self,
+        }
+    }
+
+    /// The copy of `self` to hand out when it is read from the scope it is stored in.
+    fn upgrade(self: &Rc<Closure>) -> Rc<Closure> {
+        match &self.scope {
+            Captured::Strong(_) => self.clone(),
+            Captured::Weak(_) => Rc::new(Closure {
+                code: self.code.clone(),
+                scope: Captured::Strong(self.scope()),
+            }),
+        }
+    }
+}
+
+/// Copies of a function compare equal, even if one was read from where it is stored.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Closure) -> bool {
+        Rc::ptr_eq(&self.code, &other.code) && self.scope_ptr() == other.scope_ptr()
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("params", &self.code.params)
+            .field("body", &self.code.body)
+            .finish()
+    }
+}
+
+/// An embeddable Lithp interpreter that keeps its variables between evaluations.
+pub struct Interpreter {
+    env: Environment,
+    sources: Vec<Source>,
+}
+
+/// A program the interpreter has run, kept so that errors can be shown against the text they came
+/// from even after later programs have run.
+struct Source {
+    name: String,
+    text: String,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            env: Environment::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Parses and runs `source`, returning the value of its last top-level expression, or nil if
+    /// there are none.
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, LithpError> {
+        self.eval_named("<string>", source)
+    }
+
+    /// Runs `source` like `eval_str`, naming it `name` when its errors are rendered.
+    pub fn eval_named(&mut self, name: &str, source: &str) -> Result<Value, LithpError> {
+        self.sources.push(Source {
+            name: name.to_owned(),
+            text: source.to_owned(),
+        });
+        let exprs = parse_source(source.to_owned(), self.sources.len())?;
+        run_in(&mut self.env, &exprs)
+    }
+
+    /// Formats `err` like `LithpError::render`, against the source that it came from. That may
+    /// be an earlier one than the last run, such as where a function that failed was defined.
+    pub fn render_error(&self, err: &LithpError) -> String {
+        if let LithpError::Multiple { errors } = err {
+            return errors
+                .iter()
+                .map(|err| self.render_error(err))
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        let source = err.span().source.checked_sub(1);
+        match source.and_then(|idx| self.sources.get(idx)) {
+            Some(source) => err.render(&source.name, &source.text),
+            None => err.to_string(),
+        }
+    }
+
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, LithpError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|err| LithpError::Io {
+            message: format!("Could not read {}: {}", path.display(), err),
+            span: Span::default(),
+        })?;
+        self.eval_named(&path.display().to_string(), &source)
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<Value> {
+        self.env.scope.borrow().get(name)
+    }
+
+    pub fn set_var(&mut self, name: &str, value: Value) {
+        Scope::bind(&self.env.scope, name.to_owned(), value);
+    }
+
+    /// Registers a host function that is called like a builtin, with its arguments already
+    /// evaluated. Errors it returns without a span are placed at the call.
+    pub fn register_fn<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, LithpError> + 'static,
+    {
+        Rc::make_mut(&mut self.env.builtins)
+            .host_fns
+            .insert(name.to_owned(), Rc::new(func));
+    }
+}
+
+/// Functions returned from other functions can still refer back to the top-level scope, so its
+/// variables are cleared to let them go. Any such function that the host kept will no longer
+/// find them.
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        self.env.scope.borrow_mut().vars.clear();
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
 }
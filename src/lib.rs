@@ -0,0 +1,9 @@
+pub mod error;
+pub mod interpreter;
+mod number;
+pub mod parse;
+pub mod value;
+
+pub use error::LithpError;
+pub use interpreter::Interpreter;
+pub use value::Value;
@@ -1,15 +1,73 @@
-mod interpreter;
-mod parse;
+use lithp::interpreter::to_string;
+use lithp::parse::paren_depth;
+use lithp::{Interpreter, LithpError, Value};
+use std::io::{BufRead, Write};
 
 fn main() {
     let args = std::env::args().collect::<Vec<_>>();
+    if args.len() == 1 {
+        repl();
+        return;
+    }
     if args.len() != 2 {
-        panic!(
-            "Wrong number of arguments; expected 1, got {}",
-            args.len() - 1
-        );
-    }
-    let file = std::fs::read_to_string(args[1].clone()).expect("Could not read file");
-    interpreter::run(parse::parse(file).expect("Could not parse file"))
-        .expect("Could not run file");
+        eprintln!("Usage: lithp [file]");
+        // EX_USAGE from sysexits.h, which is well clear of the codes for errors in programs
+        std::process::exit(64);
+    }
+    let mut interpreter = Interpreter::new();
+    if let Err(err) = interpreter.eval_file(&args[1]) {
+        eprintln!("{}", interpreter.render_error(&err));
+        std::process::exit(exit_code(&err));
+    }
+}
+
+fn exit_code(err: &LithpError) -> i32 {
+    match err {
+        LithpError::Runtime { .. } => 1,
+        LithpError::ParseError { .. } => 2,
+        LithpError::Arity { .. } => 3,
+        LithpError::TypeMismatch { .. } => 4,
+        LithpError::UndeclaredVariable { .. } => 5,
+        LithpError::UndeclaredFunction { .. } => 6,
+        LithpError::NotAFunction { .. } => 7,
+        LithpError::Io { .. } => 8,
+        LithpError::Overflow { .. } => 9,
+        LithpError::DivideByZero { .. } => 10,
+        LithpError::Multiple { errors } => errors.first().map_or(1, exit_code),
+    }
+}
+
+fn repl() {
+    let mut interpreter = Interpreter::new();
+    let stdin = std::io::stdin();
+    let mut input = String::new();
+    loop {
+        print!("{}", if input.is_empty() { "> " } else { ". " });
+        std::io::stdout().flush().expect("Could not flush stdout");
+        let mut line = String::new();
+        if stdin
+            .lock()
+            .read_line(&mut line)
+            .expect("Could not read line")
+            == 0
+        {
+            break;
+        }
+        input.push_str(&line);
+        // Keep reading until every group, string and block comment that was opened is closed
+        if paren_depth(&input) > 0 {
+            continue;
+        }
+        let source = std::mem::take(&mut input);
+        if source.trim().is_empty() {
+            continue;
+        }
+        match interpreter.eval_named("<repl>", &source) {
+            // Nil is what side-effecting calls like `print` return, so it isn't worth echoing
+            Ok(Value::Nil) => (),
+            Ok(value) => println!("{}", to_string(&value)),
+            Err(err) => eprintln!("{}", interpreter.render_error(&err)),
+        }
+    }
+    println!();
 }
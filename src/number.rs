@@ -0,0 +1,252 @@
+use crate::error::LithpError;
+use crate::parse::Span;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+pub fn is_number(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Int(_) | Value::Long(_) | Value::BigInt(_) | Value::Rational(_) | Value::Double(_)
+    )
+}
+
+/// Turns an exact ratio into a rational value, or into the smallest integer type that holds it if
+/// its denominator is 1.
+pub fn from_ratio(ratio: BigRational) -> Value {
+    if ratio.is_integer() {
+        from_bigint(ratio.to_integer())
+    } else {
+        Value::Rational(ratio)
+    }
+}
+
+/// Turns the result of long arithmetic into an int if it fits, like `from_bigint` does.
+fn from_long(long: i64) -> Value {
+    match i32::try_from(long) {
+        Ok(int) => Value::Int(int),
+        Err(_) => Value::Long(long),
+    }
+}
+
+pub fn from_bigint(big: BigInt) -> Value {
+    if let Some(int) = big.to_i32() {
+        Value::Int(int)
+    } else if let Some(long) = big.to_i64() {
+        Value::Long(long)
+    } else {
+        Value::BigInt(big)
+    }
+}
+
+/// Converts an integer or rational to an exact ratio.
+pub fn to_ratio(value: &Value) -> Option<BigRational> {
+    match value {
+        Value::Int(int) => Some(BigRational::from_integer(BigInt::from(*int))),
+        Value::Long(long) => Some(BigRational::from_integer(BigInt::from(*long))),
+        Value::BigInt(big) => Some(BigRational::from_integer(big.clone())),
+        Value::Rational(ratio) => Some(ratio.clone()),
+        _ => None,
+    }
+}
+
+/// Converts a number to an integer, truncating towards zero. Infinite and NaN doubles have none.
+pub fn to_bigint(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(int) => Some(BigInt::from(*int)),
+        Value::Long(long) => Some(BigInt::from(*long)),
+        Value::BigInt(big) => Some(big.clone()),
+        Value::Rational(ratio) => Some(ratio.to_integer()),
+        Value::Double(double) => BigInt::from_f64(double.trunc()),
+        _ => None,
+    }
+}
+
+pub fn to_double(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(int) => Some(*int as f64),
+        Value::Long(long) => Some(*long as f64),
+        Value::BigInt(big) => big.to_f64(),
+        Value::Rational(ratio) => ratio.to_f64(),
+        Value::Double(double) => Some(*double),
+        _ => None,
+    }
+}
+
+/// Compares two numbers exactly. A double is never rounded to compare it with an exact number,
+/// nor the other way round, so equality is transitive across types.
+pub fn compare(left: Value, right: Value) -> Option<Ordering> {
+    match (&left, &right) {
+        (Value::Double(left), Value::Double(right)) => return left.partial_cmp(right),
+        (Value::Double(double), exact) => return compare_double(*double, exact),
+        (exact, Value::Double(double)) => {
+            return compare_double(*double, exact).map(Ordering::reverse)
+        }
+        _ => (),
+    }
+    match promote(left, right) {
+        (Value::Int(left), Value::Int(right)) => Some(left.cmp(&right)),
+        (Value::Long(left), Value::Long(right)) => Some(left.cmp(&right)),
+        (Value::BigInt(left), Value::BigInt(right)) => Some(left.cmp(&right)),
+        (Value::Rational(left), Value::Rational(right)) => Some(left.cmp(&right)),
+        (Value::Double(left), Value::Double(right)) => left.partial_cmp(&right),
+        _ => None,
+    }
+}
+
+/// Compares a double with an integer or rational, or gives `None` if the double is NaN.
+fn compare_double(double: f64, exact: &Value) -> Option<Ordering> {
+    if double.is_nan() {
+        None
+    } else if double.is_infinite() {
+        Some(if double > 0.0 {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        })
+    } else {
+        Some(BigRational::from_float(double)?.cmp(&to_ratio(exact)?))
+    }
+}
+
+pub fn add(left: Value, right: Value, span: Span) -> Result<Value, LithpError> {
+    Ok(match promote(left, right) {
+        (Value::Int(left), Value::Int(right)) => match left.checked_add(right) {
+            Some(sum) => Value::Int(sum),
+            None => Value::Long(left as i64 + right as i64),
+        },
+        (Value::Long(left), Value::Long(right)) => match left.checked_add(right) {
+            Some(sum) => from_long(sum),
+            None => Value::BigInt(BigInt::from(left) + right),
+        },
+        (Value::BigInt(left), Value::BigInt(right)) => from_bigint(left + right),
+        (Value::Rational(left), Value::Rational(right)) => from_ratio(left + right),
+        (Value::Double(left), Value::Double(right)) => Value::Double(left + right),
+        _ => return Err(LithpError::type_mismatch("Not a numeric value", span)),
+    })
+}
+
+pub fn subtract(left: Value, right: Value, span: Span) -> Result<Value, LithpError> {
+    Ok(match promote(left, right) {
+        (Value::Int(left), Value::Int(right)) => match left.checked_sub(right) {
+            Some(difference) => Value::Int(difference),
+            None => Value::Long(left as i64 - right as i64),
+        },
+        (Value::Long(left), Value::Long(right)) => match left.checked_sub(right) {
+            Some(difference) => from_long(difference),
+            None => Value::BigInt(BigInt::from(left) - right),
+        },
+        (Value::BigInt(left), Value::BigInt(right)) => from_bigint(left - right),
+        (Value::Rational(left), Value::Rational(right)) => from_ratio(left - right),
+        (Value::Double(left), Value::Double(right)) => Value::Double(left - right),
+        _ => return Err(LithpError::type_mismatch("Not a numeric value", span)),
+    })
+}
+
+pub fn multiply(left: Value, right: Value, span: Span) -> Result<Value, LithpError> {
+    Ok(match promote(left, right) {
+        (Value::Int(left), Value::Int(right)) => match left.checked_mul(right) {
+            Some(product) => Value::Int(product),
+            None => Value::Long(left as i64 * right as i64),
+        },
+        (Value::Long(left), Value::Long(right)) => match left.checked_mul(right) {
+            Some(product) => from_long(product),
+            None => Value::BigInt(BigInt::from(left) * right),
+        },
+        (Value::BigInt(left), Value::BigInt(right)) => from_bigint(left * right),
+        (Value::Rational(left), Value::Rational(right)) => from_ratio(left * right),
+        (Value::Double(left), Value::Double(right)) => Value::Double(left * right),
+        _ => return Err(LithpError::type_mismatch("Not a numeric value", span)),
+    })
+}
+
+/// Divides `left` by `right`. Integers that don't divide evenly give a rational.
+pub fn divide(left: Value, right: Value, span: Span) -> Result<Value, LithpError> {
+    Ok(match promote(left, right) {
+        (Value::Int(_), Value::Int(0)) | (Value::Long(_), Value::Long(0)) => {
+            return Err(LithpError::DivideByZero { span })
+        }
+        (Value::BigInt(_), Value::BigInt(right)) if right.is_zero() => {
+            return Err(LithpError::DivideByZero { span })
+        }
+        (Value::Rational(_), Value::Rational(right)) if right.is_zero() => {
+            return Err(LithpError::DivideByZero { span })
+        }
+        (Value::Int(left), Value::Int(right)) => match left.checked_rem(right) {
+            Some(0) | None => match left.checked_div(right) {
+                Some(quotient) => Value::Int(quotient),
+                None => Value::Long(left as i64 / right as i64),
+            },
+            Some(_) => from_ratio(BigRational::new(left.into(), right.into())),
+        },
+        (Value::Long(left), Value::Long(right)) => match left.checked_rem(right) {
+            Some(0) | None => match left.checked_div(right) {
+                Some(quotient) => from_long(quotient),
+                None => Value::BigInt(BigInt::from(left) / right),
+            },
+            Some(_) => from_ratio(BigRational::new(left.into(), right.into())),
+        },
+        (Value::BigInt(left), Value::BigInt(right)) => {
+            if (&left % &right).is_zero() {
+                from_bigint(left / right)
+            } else {
+                from_ratio(BigRational::new(left, right))
+            }
+        }
+        (Value::Rational(left), Value::Rational(right)) => from_ratio(left / right),
+        (Value::Double(left), Value::Double(right)) => Value::Double(left / right),
+        _ => return Err(LithpError::type_mismatch("Not a numeric value", span)),
+    })
+}
+
+/// Converts both numbers to whichever of their types is larger.
+fn promote(left: Value, right: Value) -> (Value, Value) {
+    let mut largest_value = Value::Int(1);
+    change_if_larger(&mut largest_value, &left);
+    change_if_larger(&mut largest_value, &right);
+    (convert(left, &largest_value), convert(right, &largest_value))
+}
+
+fn change_if_larger(curr_largest: &mut Value, poss_largest: &Value) {
+    match curr_largest {
+        Value::Double(_) => (),
+        Value::Rational(_) => {
+            if let Value::Double(_) = poss_largest {
+                *curr_largest = poss_largest.clone();
+            }
+        }
+        Value::BigInt(_) => {
+            if let Value::Rational(_) | Value::Double(_) = poss_largest {
+                *curr_largest = poss_largest.clone();
+            }
+        }
+        Value::Long(_) => {
+            if let Value::BigInt(_) | Value::Rational(_) | Value::Double(_) = poss_largest {
+                *curr_largest = poss_largest.clone();
+            }
+        }
+        Value::Int(_) => {
+            if let Value::Long(_) | Value::BigInt(_) | Value::Rational(_) | Value::Double(_) =
+                poss_largest
+            {
+                *curr_largest = poss_largest.clone();
+            }
+        }
+        // Not possible
+        _ => (),
+    }
+}
+
+fn convert(value: Value, to: &Value) -> Value {
+    match (value, to) {
+        (Value::Int(int), Value::Long(_)) => Value::Long(int as i64),
+        (Value::Int(int), Value::BigInt(_)) => Value::BigInt(BigInt::from(int)),
+        (Value::Long(long), Value::BigInt(_)) => Value::BigInt(BigInt::from(long)),
+        (value, Value::Rational(_)) => Value::Rational(to_ratio(&value).unwrap()),
+        (value, Value::Double(_)) => Value::Double(to_double(&value).unwrap()),
+        (value, _) => value,
+    }
+}
@@ -1,192 +1,483 @@
-pub fn parse(prog: String) -> Result<Vec<Expr>, String> {
-    let tokens = tokenize(prog)?;
-
-    fn func_call(tokens: &Vec<Token>, idx: &mut usize) -> Result<Expr, String> {
-        let name = match tokens.first() {
-            Some(Token::Identifier(name)) => name,
-            Some(_) => return Err("Not an identifier".to_owned()),
-            None => return Err("Empty tokens".to_owned()),
+use crate::error::LithpError;
+use num_bigint::BigInt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+/// How deeply calls may nest before the parser gives up on a group, so that pathological input
+/// can't overflow the stack while parsing. Evaluation has no such limit: deep recursion that
+/// isn't in tail position can still overflow it.
+const MAX_DEPTH: usize = 256;
+
+/// Parses `prog` into its top-level expressions. If there are errors, parsing carries on past each
+/// one so that they can all be reported together.
+pub fn parse(prog: String) -> Result<Vec<Expr>, LithpError> {
+    parse_source(prog, 0)
+}
+
+/// Parses `prog` like `parse`, marking each span as being in the source numbered `source`.
+pub(crate) fn parse_source(prog: String, source: usize) -> Result<Vec<Expr>, LithpError> {
+    let mut parser = Parser {
+        tokens: tokenize_source(prog, source)?.into_iter().peekable(),
+        depth: 0,
+        errors: Vec::new(),
+    };
+    let mut exprs = Vec::new();
+    while parser.tokens.peek().is_some() {
+        exprs.extend(parser.value());
+    }
+    let mut errors = parser.errors;
+    match errors.len() {
+        0 => Ok(exprs),
+        1 => Err(errors.remove(0)),
+        _ => Err(LithpError::Multiple { errors }),
+    }
+}
+
+/// A cursor over the tokens of a program. Each token is visited once.
+struct Parser {
+    tokens: Peekable<IntoIter<Token>>,
+    depth: usize,
+    errors: Vec<LithpError>,
+}
+
+impl Parser {
+    /// Parses the value at the cursor, or records an error and returns `None` if there isn't one.
+    fn value(&mut self) -> Option<Expr> {
+        let token = self.tokens.next()?;
+        let kind = match token.kind {
+            TokenKind::Identifier(name) => {
+                // The result of a call can itself be called, as in `make(1)(2)`. Each call in a
+                // chain nests the calls before it, so counts towards the depth
+                let depth = self.depth;
+                let mut kind = ExprKind::Identifier(name);
+                while self.next_is(&TokenKind::GroupLeft) {
+                    let args = match self.args() {
+                        Some(args) => args,
+                        None => {
+                            // Skip the rest of the chain rather than report each call in it
+                            while self.next_is(&TokenKind::GroupLeft) {
+                                self.tokens.next();
+                                self.skip_group();
+                            }
+                            self.depth = depth;
+                            return None;
+                        }
+                    };
+                    kind = ExprKind::FuncCall(Box::new(Expr::new(kind, token.span)), args);
+                    self.depth += 1;
+                }
+                self.depth = depth;
+                kind
+            }
+            TokenKind::StringLiteral(string) => ExprKind::StringLiteral(string),
+            TokenKind::IntLiteral(int) => ExprKind::IntLiteral(int),
+            TokenKind::LongLiteral(long) => ExprKind::LongLiteral(long),
+            TokenKind::BigIntLiteral(big) => ExprKind::BigIntLiteral(big),
+            TokenKind::DoubleLiteral(double) => ExprKind::DoubleLiteral(double),
+            TokenKind::BooleanLiteral(boolean) => ExprKind::BooleanLiteral(boolean),
+            TokenKind::NilLiteral => ExprKind::NilLiteral,
+            TokenKind::GroupLeft => {
+                self.errors.push(LithpError::parse(
+                    "Expected a function before parenthesis",
+                    token.span,
+                ));
+                self.skip_group();
+                return None;
+            }
+            TokenKind::GroupRight => {
+                self.errors.push(LithpError::parse(
+                    "Unexpected closing parenthesis",
+                    token.span,
+                ));
+                return None;
+            }
         };
-        if tokens[1] != Token::GroupLeft {
-            return Err("Missing opening parenthesis".to_owned());
-        }
-        if *tokens.last().unwrap() != Token::GroupRight {
-            return Err("Missing closing parenthesis".to_owned());
+        Some(Expr::new(kind, token.span))
+    }
+
+    /// Parses a parenthesized argument list starting at the cursor.
+    fn args(&mut self) -> Option<Vec<Expr>> {
+        let open = self.tokens.next()?.span;
+        if self.depth == MAX_DEPTH {
+            self.errors
+                .push(LithpError::parse("Calls are nested too deeply", open));
+            self.skip_group();
+            return None;
         }
+        self.depth += 1;
         let mut args = Vec::new();
-        while tokens[*idx] != Token::GroupRight {
-            args.push(match &tokens[*idx] {
-                Token::Identifier(name) => {
-                    if tokens[*idx + 1] == Token::GroupLeft {
-                        let mut start = 2;
-                        let result = func_call(&tokens[*idx..].to_vec(), &mut start)?;
-                        *idx += start;
-                        result
-                    } else {
-                        Expr::Identifier(name.clone())
-                    }
-                }
-                Token::StringLiteral(string) => Expr::StringLiteral(string.clone()),
-                Token::IntLiteral(int) => Expr::IntLiteral(*int),
-                Token::LongLiteral(long) => Expr::LongLiteral(*long),
-                Token::DoubleLiteral(double) => Expr::DoubleLiteral(*double),
-                Token::BooleanLiteral(boolean) => Expr::BooleanLiteral(*boolean),
-                _ => return Err("Unexpected token, expected value".to_owned()),
-            });
-            *idx += 1;
-        }
-        Ok(Expr::FuncCall(name.clone(), args))
+        loop {
+            if self.next_is(&TokenKind::GroupRight) {
+                self.tokens.next();
+                break;
+            }
+            if self.tokens.peek().is_none() {
+                self.errors
+                    .push(LithpError::parse("Missing closing parenthesis", open));
+                break;
+            }
+            args.extend(self.value());
+        }
+        self.depth -= 1;
+        Some(args)
     }
 
-    let mut exprs = Vec::new();
-    let mut total_offset = 0;
-    while total_offset < tokens.len() {
-        let mut start = 2;
-        exprs.push(func_call(&tokens[total_offset..].to_vec(), &mut start)?);
-        total_offset += start + 1;
+    /// Skips the rest of a group whose opening parenthesis was just consumed.
+    fn skip_group(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.tokens.next().map(|token| token.kind) {
+                Some(TokenKind::GroupLeft) => depth += 1,
+                Some(TokenKind::GroupRight) => depth -= 1,
+                Some(_) => (),
+                None => break,
+            }
+        }
     }
-    Ok(exprs)
+
+    fn next_is(&mut self, kind: &TokenKind) -> bool {
+        self.tokens.peek().map(|token| &token.kind) == Some(kind)
+    }
+}
+
+/// A location in the source, with 1-based line and column and a length in characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+    pub len: usize,
+    /// Which of an interpreter's sources the span is in, numbered from 1, or 0 for source that
+    /// was parsed on its own
+    pub source: usize,
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum Expr {
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Expr {
+        Expr { kind, span }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
     Identifier(String),
     StringLiteral(String),
     IntLiteral(i32),
     LongLiteral(i64),
+    BigIntLiteral(BigInt),
     DoubleLiteral(f64),
     BooleanLiteral(bool),
-    FuncCall(String, Vec<Expr>),
+    NilLiteral,
+    /// A call of the function that the first expression evaluates to
+    FuncCall(Box<Expr>, Vec<Expr>),
 }
 
-pub fn tokenize(prog: String) -> Result<Vec<Token>, String> {
-    let bytes = prog.as_bytes();
+/// Splits `prog` into tokens. Line comments run from `;` to the end of the line and block comments
+/// are delimited by `#|` and `|#` and may nest; neither produces tokens. Any Unicode whitespace
+/// separates tokens.
+pub fn tokenize(prog: String) -> Result<Vec<Token>, LithpError> {
+    tokenize_source(prog, 0)
+}
+
+fn tokenize_source(prog: String, source: usize) -> Result<Vec<Token>, LithpError> {
+    let chars = prog.chars().collect::<Vec<_>>();
     let mut tokens = Vec::new();
     let mut value = "".to_owned();
+    let mut value_span = Span::default();
     let mut in_str = false;
-    for byte in bytes {
-        let character = *byte as char;
-        let mut push_val = || {
-            let value = value.clone();
-            if value.parse::<i32>().is_ok() {
-                tokens.push(Token::IntLiteral(value.parse().unwrap()));
-            } else if value.parse::<i64>().is_ok() {
-                tokens.push(Token::LongLiteral(value.parse().unwrap()));
-            } else if value.parse::<f64>().is_ok() {
-                tokens.push(Token::DoubleLiteral(value.parse().unwrap()));
-            } else if value.len() >= 2
-                && *value.as_bytes().first().unwrap() as char == '"'
-                && *value.as_bytes().last().unwrap() as char == '"'
-            {
-                let length = value.as_bytes().len();
-                tokens.push(Token::StringLiteral(value[1..length - 1].to_owned()));
-            } else {
-                if value == "true".to_owned() {
-                    tokens.push(Token::BooleanLiteral(true));
-                } else if value == "false".to_owned() {
-                    tokens.push(Token::BooleanLiteral(false));
-                } else {
-                    tokens.push(Token::Identifier(value));
-                }
-            }
+    let mut string = "".to_owned();
+    let mut string_start = 0;
+    let mut string_span = Span::default();
+    let mut in_line_comment = false;
+    // Block comments nest, so track how deep we are and where the outermost one opened
+    let mut comment_depth = 0;
+    let mut comment_span = Span::default();
+    let mut skip = 0;
+    let mut line = 1;
+    let mut col = 1;
+    for (idx, &character) in chars.iter().enumerate() {
+        let next = chars.get(idx + 1).copied();
+        let span = Span {
+            line,
+            col,
+            len: 1,
+            source,
         };
-        let token = match character {
-            '(' => {
-                if in_str {
-                    value.push(character);
-                    continue;
+        if character == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        if in_str {
+            match character {
+                '"' => {
+                    in_str = false;
+                    tokens.push(Token {
+                        kind: TokenKind::StringLiteral(std::mem::take(&mut string)),
+                        span: Span {
+                            len: idx - string_start + 1,
+                            ..string_span
+                        },
+                    });
                 }
-                if !value.is_empty() {
-                    push_val();
+                '\\' => {
+                    let (escaped, len) = escape(&chars[idx + 1..], span)?;
+                    string.push(escaped);
+                    skip = len;
                 }
-                value = "".to_owned();
-                Some(Token::GroupLeft)
+                _ => string.push(character),
             }
-            ')' => {
-                if in_str {
-                    value.push(character);
-                    continue;
-                }
-                if !value.is_empty() {
-                    push_val();
-                }
-                value = "".to_owned();
-                Some(Token::GroupRight)
+            continue;
+        }
+        if in_line_comment {
+            in_line_comment = character != '\n';
+            continue;
+        }
+        if comment_depth > 0 {
+            match (character, next) {
+                ('#', Some('|')) => comment_depth += 1,
+                ('|', Some('#')) => comment_depth -= 1,
+                _ => continue,
             }
-            '"' => {
-                in_str = !in_str;
-                value.push(character);
-                None
+            skip = 1;
+            continue;
+        }
+        if value.is_empty() {
+            value_span = span;
+        }
+        let token = match character {
+            '(' => Some(TokenKind::GroupLeft),
+            ')' => Some(TokenKind::GroupRight),
+            '"' | ';' => None,
+            '#' if next == Some('|') => None,
+            '|' if next == Some('#') => {
+                return Err(LithpError::parse(
+                    "Unmatched end of block comment",
+                    Span { len: 2, ..span },
+                ))
             }
-            ' ' => {
-                if in_str {
-                    value.push(character);
-                    continue;
-                }
-                if !value.is_empty() {
-                    push_val();
-                }
-                value = "".to_owned();
-                None
+            _ if character.is_whitespace() => None,
+            '[' | ']' | '{' | '}' | ',' | '\'' | '`' | '\\' => {
+                return Err(LithpError::parse(
+                    format!("Unexpected character '{}'", character),
+                    span,
+                ))
             }
-            '\n' => {
-                if in_str {
-                    value.push(character);
-                    continue;
-                }
-                if !value.is_empty() {
-                    push_val();
-                }
-                value = "".to_owned();
-                None
+            _ if character.is_control() => {
+                return Err(LithpError::parse(
+                    format!("Unexpected character {:?}", character),
+                    span,
+                ))
             }
             _ => {
                 value.push(character);
-                None
+                continue;
             }
         };
-        if let Some(token) = token {
-            tokens.push(token);
+        if !value.is_empty() {
+            push_value(&mut tokens, &value, value_span);
         }
-    }
-    let mut push_val = || {
-        let value = value.clone();
-        if value.parse::<i32>().is_ok() {
-            tokens.push(Token::IntLiteral(value.parse().unwrap()));
-        } else if value.parse::<i64>().is_ok() {
-            tokens.push(Token::LongLiteral(value.parse().unwrap()));
-        } else if value.parse::<f64>().is_ok() {
-            tokens.push(Token::DoubleLiteral(value.parse().unwrap()));
-        } else if value.len() >= 2
-            && *value.as_bytes().first().unwrap() as char == '"'
-            && *value.as_bytes().last().unwrap() as char == '"'
-        {
-            let length = value.as_bytes().len();
-            tokens.push(Token::StringLiteral(value[1..length - 1].to_owned()));
-        } else {
-            if value == "true".to_owned() {
-                tokens.push(Token::BooleanLiteral(true));
-            } else if value == "false".to_owned() {
-                tokens.push(Token::BooleanLiteral(false));
-            } else {
-                tokens.push(Token::Identifier(value));
+        value = "".to_owned();
+        match character {
+            '"' => {
+                in_str = true;
+                string_start = idx;
+                string_span = span;
             }
+            ';' => in_line_comment = true,
+            '#' => {
+                comment_depth = 1;
+                comment_span = Span { len: 2, ..span };
+                skip = 1;
+            }
+            _ => (),
         }
-    };
+        if let Some(kind) = token {
+            tokens.push(Token { kind, span });
+        }
+    }
+    if in_str {
+        return Err(LithpError::parse(
+            "Unterminated string literal",
+            string_span,
+        ));
+    }
+    if comment_depth > 0 {
+        return Err(LithpError::parse(
+            "Unterminated block comment",
+            comment_span,
+        ));
+    }
     if !value.is_empty() {
-        push_val();
+        push_value(&mut tokens, &value, value_span);
     }
     Ok(tokens)
 }
 
+/// Reads the escape sequence following a backslash at `span`, returning the escaped character and
+/// the number of characters after the backslash that it used.
+fn escape(rest: &[char], span: Span) -> Result<(char, usize), LithpError> {
+    let escaped = match rest.first() {
+        Some('"') => '"',
+        Some('\\') => '\\',
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('u') => return unicode_escape(rest, span),
+        Some(other) => {
+            return Err(LithpError::parse(
+                format!("Unknown escape sequence \\{}", other),
+                Span { len: 2, ..span },
+            ))
+        }
+        None => return Err(LithpError::parse("Unterminated escape sequence", span)),
+    };
+    Ok((escaped, 1))
+}
+
+/// Reads a `u{...}` escape of one to six hex digits.
+fn unicode_escape(rest: &[char], span: Span) -> Result<(char, usize), LithpError> {
+    let close = rest.iter().take(9).position(|&character| character == '}');
+    let digits = match close {
+        Some(close)
+            if rest.get(1) == Some(&'{')
+                && close > 2
+                && rest[2..close].iter().all(char::is_ascii_hexdigit) =>
+        {
+            &rest[2..close]
+        }
+        _ => {
+            return Err(LithpError::parse(
+                "Expected \\u{...} with one to six hex digits",
+                Span { len: 2, ..span },
+            ))
+        }
+    };
+    let len = digits.len() + 3;
+    let code = u32::from_str_radix(&digits.iter().collect::<String>(), 16).ok();
+    match code.and_then(char::from_u32) {
+        Some(escaped) => Ok((escaped, len)),
+        None => Err(LithpError::parse(
+            "Invalid unicode escape",
+            Span {
+                len: len + 1,
+                ..span
+            },
+        )),
+    }
+}
+
+fn push_value(tokens: &mut Vec<Token>, value: &str, span: Span) {
+    let span = Span {
+        len: value.chars().count(),
+        ..span
+    };
+    let kind = if let Ok(int) = value.parse::<i32>() {
+        TokenKind::IntLiteral(int)
+    } else if let Ok(long) = value.parse::<i64>() {
+        TokenKind::LongLiteral(long)
+    } else if is_integer(value) {
+        TokenKind::BigIntLiteral(value.parse::<BigInt>().unwrap())
+    } else if let Some(double) = parse_double(value) {
+        TokenKind::DoubleLiteral(double)
+    } else if value == "true" {
+        TokenKind::BooleanLiteral(true)
+    } else if value == "false" {
+        TokenKind::BooleanLiteral(false)
+    } else if value == "nil" {
+        TokenKind::NilLiteral
+    } else {
+        TokenKind::Identifier(value.to_owned())
+    };
+    tokens.push(Token { kind, span });
+}
+
+/// Reads `text` as a double if it is written as a number. `f64`'s own parsing also accepts words
+/// like `inf` and `NaN`, which are left as identifiers.
+fn parse_double(text: &str) -> Option<f64> {
+    let unsigned = text.strip_prefix(|sign| sign == '+' || sign == '-').unwrap_or(text);
+    if unsigned.starts_with(|first: char| first.is_ascii_digit() || first == '.') {
+        text.parse::<f64>().ok()
+    } else {
+        None
+    }
+}
+
+/// Whether `text` is an optional sign followed by ASCII digits. `BigInt`'s own parsing is more
+/// lenient, accepting underscores between digits.
+pub(crate) fn is_integer(text: &str) -> bool {
+    let digits = text.strip_prefix(|sign| sign == '+' || sign == '-').unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// How many groups, strings and block comments `input` leaves open, so that the REPL knows to keep
+/// reading. It is negative if `input` closes a group that was never opened, which more input can't
+/// fix.
+pub fn paren_depth(input: &str) -> i32 {
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut in_line_comment = false;
+    let mut comment_depth = 0;
+    let mut chars = input.chars().peekable();
+    while let Some(character) = chars.next() {
+        if in_line_comment {
+            in_line_comment = character != '\n';
+            continue;
+        }
+        let next = chars.peek().copied();
+        match character {
+            '#' if !in_str && next == Some('|') => {
+                comment_depth += 1;
+                chars.next();
+            }
+            '|' if comment_depth > 0 && next == Some('#') => {
+                comment_depth -= 1;
+                chars.next();
+            }
+            _ if comment_depth > 0 => (),
+            '\\' if in_str => {
+                chars.next();
+            }
+            ';' if !in_str => in_line_comment = true,
+            '"' => in_str = !in_str,
+            '(' if !in_str => depth += 1,
+            ')' if !in_str => {
+                depth -= 1;
+                if depth < 0 {
+                    return depth;
+                }
+            }
+            _ => (),
+        }
+    }
+    depth + comment_depth + i32::from(in_str)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
 #[derive(Clone, Debug, PartialEq)]
-pub enum Token {
+pub enum TokenKind {
     GroupLeft,
     GroupRight,
     Identifier(String),
     StringLiteral(String),
     IntLiteral(i32),
     LongLiteral(i64),
+    BigIntLiteral(BigInt),
     DoubleLiteral(f64),
     BooleanLiteral(bool),
+    NilLiteral,
 }
@@ -0,0 +1,56 @@
+use crate::interpreter::Closure;
+use crate::number;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::rc::Rc;
+
+/// A value produced by evaluating an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Long(i64),
+    BigInt(BigInt),
+    Rational(BigRational),
+    Double(f64),
+    String(String),
+    Boolean(bool),
+    Closure(Rc<Closure>),
+    /// A builtin or host function, by name
+    Builtin(String),
+    List(List),
+    Dict(Dict),
+    Nil,
+}
+
+/// An immutable list. Cloning one, and taking its tail or adding to its front, don't copy its
+/// items, so walking a list with `head` and `tail` or building one with `cons` is fast.
+pub type List = im_rc::Vector<Value>;
+
+/// An immutable map from keys to values. Each entry keeps the key it was first given alongside
+/// its value, so `1` and `1.0` share an entry but the dict still lists the key as `1`.
+pub type Dict = im_rc::HashMap<Key, (Value, Value)>;
+
+/// A dict key, normalized so that keys are equal exactly when they are `==`: numbers by their
+/// exact value, whatever their type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    NegInfinity,
+    Number(BigRational),
+    Infinity,
+    String(String),
+    Boolean(bool),
+}
+
+impl Key {
+    /// The key for `value`, if it is a string, a boolean or a number other than NaN.
+    pub fn new(value: &Value) -> Option<Key> {
+        match value {
+            Value::String(string) => Some(Key::String(string.clone())),
+            Value::Boolean(boolean) => Some(Key::Boolean(*boolean)),
+            Value::Double(double) if *double == f64::INFINITY => Some(Key::Infinity),
+            Value::Double(double) if *double == f64::NEG_INFINITY => Some(Key::NegInfinity),
+            Value::Double(double) => BigRational::from_float(*double).map(Key::Number),
+            _ => number::to_ratio(value).map(Key::Number),
+        }
+    }
+}
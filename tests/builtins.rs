@@ -0,0 +1,560 @@
+use lithp::interpreter::to_string;
+use lithp::Interpreter;
+
+/// Checks that each source evaluates to the value that prints as the expected string.
+fn assert_values(cases: Vec<(&str, &str)>) {
+    for (source, expected) in cases {
+        match Interpreter::new().eval_str(source) {
+            Ok(value) => assert_eq!(to_string(&value), expected, "evaluating {:?}", source),
+            Err(err) => panic!("{:?} failed: {}", source, err),
+        }
+    }
+}
+
+/// Checks that each source fails with the expected error at the expected position.
+fn assert_errors(cases: Vec<(&str, (usize, usize), &str)>) {
+    for (source, (line, col), message) in cases {
+        match Interpreter::new().eval_str(source) {
+            Ok(value) => panic!("{:?} evaluated to {:?}", source, value),
+            Err(err) => assert_eq!(
+                err.to_string(),
+                format!("{}:{}: {}", line, col, message),
+                "evaluating {:?}",
+                source
+            ),
+        }
+    }
+}
+
+#[test]
+fn logic() {
+    assert_values(vec![
+        ("and(true true)", "true"),
+        ("and(true false true)", "false"),
+        ("or(false true)", "true"),
+        ("or(false false)", "false"),
+        ("not(false)", "true"),
+        ("and()", "true"),
+        ("or()", "false"),
+        // Arguments after the one that decides the result aren't evaluated
+        ("and(false undefinedVar)", "false"),
+        ("or(true undefinedVar)", "true"),
+        ("and(false 1)", "false"),
+        ("or(true \"a\")", "true"),
+    ]);
+    assert_errors(vec![
+        ("and(true 1)", (1, 10), "Not a boolean value"),
+        ("or(false \"a\")", (1, 10), "Not a boolean value"),
+        ("and(nil)", (1, 5), "Not a boolean value"),
+        ("not(1)", (1, 5), "Not a boolean value"),
+        (
+            "and(true undefinedVar)",
+            (1, 10),
+            "Undeclared variable undefinedVar",
+        ),
+        (
+            "not()",
+            (1, 1),
+            "Wrong number of arguments; expected 1, got 0",
+        ),
+        (
+            "not(true false)",
+            (1, 1),
+            "Wrong number of arguments; expected 1, got 2",
+        ),
+    ]);
+}
+
+#[test]
+fn cond_and_switch() {
+    assert_values(vec![
+        ("cond(true 1 2)", "1"),
+        ("cond(false 1 false 2 3)", "3"),
+        ("cond(false 1 true 2 3)", "2"),
+        ("cond(\"only\")", "only"),
+        ("switch(2 1 \"a\" 2 \"b\" \"c\")", "b"),
+        ("switch(5 1 \"a\" \"c\")", "c"),
+        ("switch(1.0 1 \"one\" \"other\")", "one"),
+        ("switch(\"x\" \"fallback\")", "fallback"),
+        // Only the tests, cases and branch that are needed are evaluated
+        ("cond(true 1 undefinedVar)", "1"),
+        ("cond(false undefinedVar 2)", "2"),
+        ("cond(true 1 undefinedVar 2 3)", "1"),
+        ("switch(1 1 \"a\" undefinedVar)", "a"),
+        ("switch(1 2 undefinedVar 1 \"b\" \"c\")", "b"),
+        ("switch(1 1 \"a\" undefinedVar \"b\" \"c\")", "a"),
+    ]);
+    assert_errors(vec![
+        (
+            "cond()",
+            (1, 1),
+            "Wrong number of arguments; expected an odd number, got 0",
+        ),
+        (
+            "cond(true 1)",
+            (1, 1),
+            "Wrong number of arguments; expected an odd number, got 2",
+        ),
+        (
+            "switch()",
+            (1, 1),
+            "Wrong number of arguments; expected an even number of at least 2, got 0",
+        ),
+        (
+            "switch(1)",
+            (1, 1),
+            "Wrong number of arguments; expected an even number of at least 2, got 1",
+        ),
+        (
+            "switch(1 2 3)",
+            (1, 1),
+            "Wrong number of arguments; expected an even number of at least 2, got 3",
+        ),
+        ("cond(1 2 3)", (1, 6), "Not a boolean value"),
+        (
+            "cond(false 1 undefinedVar)",
+            (1, 14),
+            "Undeclared variable undefinedVar",
+        ),
+        (
+            "switch(undefinedVar 1 2 3)",
+            (1, 8),
+            "Undeclared variable undefinedVar",
+        ),
+    ]);
+}
+
+#[test]
+fn strings() {
+    assert_values(vec![
+        // Positions and lengths count characters, not bytes
+        ("len(\"héllo✓\")", "6"),
+        ("substr(\"héllo✓\" 1 3)", "él"),
+        ("substr(\"héllo✓\" 5)", "✓"),
+        ("substr(\"héllo✓\" 6)", ""),
+        ("indexOf(\"héllo✓\" \"l\")", "2"),
+        ("indexOf(\"héllo✓\" \"✓\")", "5"),
+        ("substr(\"héllo✓\" indexOf(\"héllo✓\" \"l\"))", "llo✓"),
+        ("indexOf(\"abc\" \"z\")", "nil"),
+        ("indexOf(\"abc\" \"\")", "0"),
+        ("format(\"{} + {} = {}\" 1 2 3)", "1 + 2 = 3"),
+        ("format(\"{{}} {}\" 1)", "{} 1"),
+        ("format(\"}}{{\")", "}{"),
+        ("format(\"{{{}}}\" \"x\")", "{x}"),
+    ]);
+    assert_errors(vec![
+        (
+            "substr(\"héllo✓\" 2 7)",
+            (1, 1),
+            "Range 2 to 7 is out of bounds for length 6",
+        ),
+        (
+            "substr(\"abc\" 2 1)",
+            (1, 1),
+            "Range 2 to 1 is out of bounds for length 3",
+        ),
+        (
+            "substr(\"abc\" -(0 1))",
+            (1, 14),
+            "Expected a non-negative integer index",
+        ),
+        (
+            "format(\"{} {}\" 1)",
+            (1, 1),
+            "Format string has 2 placeholders but got 1 values",
+        ),
+        (
+            "format(\"{}\" 1 2)",
+            (1, 1),
+            "Format string has 1 placeholders but got 2 values",
+        ),
+        (
+            "format(\"a { b\")",
+            (1, 8),
+            "Unmatched '{' in format string",
+        ),
+        (
+            "format(\"a } b\")",
+            (1, 8),
+            "Unmatched '}' in format string",
+        ),
+        ("format(1)", (1, 8), "Not a string"),
+    ]);
+}
+
+#[test]
+fn conversions() {
+    assert_values(vec![
+        ("toInt(\" 12 \")", "12"),
+        ("toInt(\"-7\")", "-7"),
+        ("toInt(/(7 2))", "3"),
+        ("toInt(-2.9)", "-2"),
+        ("toLong(\"3000000000\")", "3000000000"),
+        ("toDouble(\"2.5\")", "2.5"),
+        ("toDouble(/(1 4))", "0.25"),
+        ("toBool(\"false\")", "false"),
+        ("typeOf(1)", "int"),
+        ("typeOf(3000000000)", "long"),
+        ("typeOf(100000000000000000000)", "bigint"),
+        ("typeOf(/(1 3))", "rational"),
+        ("typeOf(1.5)", "double"),
+        ("typeOf(\"a\")", "string"),
+        ("typeOf(true)", "bool"),
+        ("typeOf(func(x x))", "func"),
+        ("typeOf(+)", "builtin"),
+        ("typeOf(list())", "list"),
+        ("typeOf(dict())", "dict"),
+        ("typeOf(nil)", "nil"),
+    ]);
+    assert_errors(vec![
+        ("toInt(\"12x\")", (1, 7), "Cannot convert 12x to an integer"),
+        (
+            "toInt(\"1_000\")",
+            (1, 7),
+            "Cannot convert 1_000 to an integer",
+        ),
+        ("toInt(1e300)", (1, 7), "Integer overflow"),
+        ("toInt(\"3000000000\")", (1, 7), "Integer overflow"),
+        ("toLong(1e300)", (1, 8), "Integer overflow"),
+        (
+            "toInt(/(1.0 0.0))",
+            (1, 7),
+            "Cannot convert inf to an integer",
+        ),
+        ("toInt(true)", (1, 7), "Expected a number or string"),
+        ("toDouble(\"x\")", (1, 10), "Cannot convert x to a double"),
+        ("toBool(\"yes\")", (1, 8), "Cannot convert yes to a boolean"),
+    ]);
+}
+
+#[test]
+fn functions_as_values() {
+    assert_values(vec![
+        // A function's body can be any expression, not only a call
+        ("=(id func(x x)) id(5)", "5"),
+        ("=(id func(x x)) id(id)(\"a\")", "a"),
+        ("=(answer func(42)) answer()", "42"),
+        ("=(twice func(f x f(f(x)))) twice(func(n *(n 3)) 2)", "18"),
+        ("=(make func(n func(x +(n x)))) make(1)(2)", "3"),
+        ("func(x y -(x y))(5 2)", "3"),
+        (
+            "=(compose func(f g func(x f(g(x))))) compose(func(x -(0 x)) func(x *(x 2)))(4)",
+            "-8",
+        ),
+        // Builtins are values too, and can be bound and passed like functions
+        ("=(plus +) plus(1 2)", "3"),
+        ("=(apply func(f a b f(a b))) apply(+ 1 2)", "3"),
+        ("=(apply func(f a b f(a b))) apply(* 4 5)", "20"),
+        ("reduce(+ 0 list(1 2 3))", "6"),
+        ("map(typeOf list(1 \"a\"))", "[int string]"),
+        ("==(+ +)", "true"),
+    ]);
+    assert_errors(vec![
+        ("=(x 1) x()", (1, 8), "x is not a function"),
+        (
+            "func(x x)(1 2)",
+            (1, 1),
+            "Wrong number of arguments; expected 1, got 2",
+        ),
+        (
+            "=(apply func(f f(true))) apply(=)",
+            (1, 16),
+            "= is a special form, so it can only be called by name",
+        ),
+        (
+            "=(choose ifElse) choose(true 1 2)",
+            (1, 18),
+            "ifElse is a special form, so it can only be called by name",
+        ),
+        (
+            "map(and list(true))",
+            (1, 5),
+            "and is a special form, so it can only be called by name",
+        ),
+    ]);
+}
+
+#[test]
+fn integer_arithmetic() {
+    assert_values(vec![
+        ("+(1 2 3)", "6"),
+        ("-(10 3 2)", "5"),
+        ("*(2 3 4)", "24"),
+        ("/(6 3)", "2"),
+        // Results that overflow an int are promoted to a long, and shrink back when they fit
+        ("+(2147483647 1)", "2147483648"),
+        ("typeOf(+(2147483647 1))", "long"),
+        ("-(0 2147483647 2)", "-2147483649"),
+        ("typeOf(*(65536 65536))", "long"),
+        ("typeOf(-(+(2147483647 1) 1))", "int"),
+        ("typeOf(/(4294967296 4))", "int"),
+        ("/(-2147483648 -1)", "2147483648"),
+        ("typeOf(/(-2147483648 -1))", "long"),
+        // Longs above 2^53 stay exact
+        ("+(9007199254740993 0)", "9007199254740993"),
+        ("-(9007199254740993 2)", "9007199254740991"),
+    ]);
+    assert_errors(vec![
+        ("/(1 0)", (1, 5), "Division by zero"),
+        ("/(3000000000 0)", (1, 14), "Division by zero"),
+        ("/(10 2 0)", (1, 8), "Division by zero"),
+        ("+(1 \"a\")", (1, 5), "Not a numeric value"),
+    ]);
+}
+
+#[test]
+fn bigint_arithmetic() {
+    assert_values(vec![
+        // Literals too large for a long are read exactly
+        (
+            "123456789012345678901234567890",
+            "123456789012345678901234567890",
+        ),
+        ("typeOf(123456789012345678901234567890)", "bigint"),
+        ("typeOf(9223372036854775807)", "long"),
+        ("typeOf(9223372036854775808)", "bigint"),
+        (
+            "-(0 123456789012345678901234567890)",
+            "-123456789012345678901234567890",
+        ),
+        // Long results that overflow are promoted, and shrink back when they fit
+        ("+(9223372036854775807 1)", "9223372036854775808"),
+        ("typeOf(+(9223372036854775807 1))", "bigint"),
+        ("*(4294967296 4294967296)", "18446744073709551616"),
+        ("/(-9223372036854775808 -1)", "9223372036854775808"),
+        ("typeOf(-(+(9223372036854775807 1) 1))", "long"),
+        (
+            "typeOf(/(100000000000000000000 50000000000000000000))",
+            "int",
+        ),
+        ("/(100000000000000000000 10)", "10000000000000000000"),
+        ("/(100000000000000000000 3)", "100000000000000000000/3"),
+    ]);
+    assert_errors(vec![(
+        "/(100000000000000000000 0)",
+        (1, 25),
+        "Division by zero",
+    )]);
+}
+
+#[test]
+fn rational_arithmetic() {
+    assert_values(vec![
+        ("/(1 3)", "1/3"),
+        ("/(2 6)", "1/3"),
+        ("/(-1 3)", "-1/3"),
+        ("/(1 -3)", "-1/3"),
+        ("/(7 2)", "7/2"),
+        ("typeOf(/(6 3))", "int"),
+        ("+(/(1 3) /(1 6))", "1/2"),
+        ("-(/(1 2) /(1 3))", "1/6"),
+        ("*(/(2 3) /(3 4))", "1/2"),
+        ("/(/(1 3) /(2 3))", "1/2"),
+        ("+(/(1 3) 1)", "4/3"),
+        // Rationals that come out whole shrink back to integers, and doubles take over
+        ("+(/(1 3) /(2 3))", "1"),
+        ("typeOf(+(/(1 3) /(2 3)))", "int"),
+        ("+(/(1 2) 0.25)", "0.75"),
+        ("==(/(1 3) /(2 6))", "true"),
+        ("==(/(1 2) 0.5)", "true"),
+        ("==(/(1 3) 0.3333333333333333)", "false"),
+        ("<(/(1 3) /(1 2))", "true"),
+        (">(/(1 3) 0.3)", "true"),
+        ("<=(/(1 2) /(2 4) 1)", "true"),
+        ("numerator(/(2 6))", "1"),
+        ("denominator(/(2 6))", "3"),
+        ("numerator(/(-2 6))", "-1"),
+        ("denominator(/(-2 6))", "3"),
+        ("numerator(5)", "5"),
+        ("denominator(5)", "1"),
+        ("toDouble(/(1 4))", "0.25"),
+    ]);
+    assert_errors(vec![
+        ("/(/(1 3) 0)", (1, 10), "Division by zero"),
+        (
+            "numerator(1.5)",
+            (1, 11),
+            "Not an integer or rational value",
+        ),
+        (
+            "denominator(\"a\")",
+            (1, 13),
+            "Not an integer or rational value",
+        ),
+    ]);
+}
+
+#[test]
+fn lists() {
+    assert_values(vec![
+        ("list()", "[]"),
+        ("list(1 \"a\" list(2 3) nil)", "[1 a [2 3] nil]"),
+        ("cons(1 list(2 3))", "[1 2 3]"),
+        ("cons(list() list())", "[[]]"),
+        ("head(list(1 2))", "1"),
+        ("tail(list(1 2 3))", "[2 3]"),
+        ("tail(list(1))", "[]"),
+        ("nth(list(1 2 3) 2)", "3"),
+        ("append()", "[]"),
+        ("append(list(1) list() list(2 3))", "[1 2 3]"),
+        ("reverse(list(1 2 3))", "[3 2 1]"),
+        ("reverse(list())", "[]"),
+        ("isEmpty(list())", "true"),
+        ("isEmpty(list(nil))", "false"),
+        ("isEmpty(tail(list(1)))", "true"),
+        ("map(func(x *(x x)) list(1 2 3))", "[1 4 9]"),
+        ("map(func(x x) list())", "[]"),
+        ("filter(func(x >(x 1)) list(1 2 3))", "[2 3]"),
+        // The filter function is never called on an empty list
+        ("filter(func(x 1) list())", "[]"),
+        ("reduce(func(acc x +(acc x)) 0 list(1 2 3))", "6"),
+        (
+            "reduce(func(acc x cons(x acc)) list() list(1 2 3))",
+            "[3 2 1]",
+        ),
+        ("reduce(+ 10 list())", "10"),
+        // Lists are values, so building on one leaves it as it was
+        (
+            "=(xs list(1 2)) cons(0 xs) append(xs xs) tail(xs) xs",
+            "[1 2]",
+        ),
+        ("==(list(1 2) list(1.0 2))", "true"),
+        ("==(list(1 2) list(1 2 3))", "false"),
+        ("==(list(1) list(\"a\"))", "false"),
+        ("==(list() list())", "true"),
+        ("==(list(list(1)) list(list(1)))", "true"),
+        ("<(list(1 2) list(1 3))", "true"),
+        ("<(list(1 2) list(1 2 3))", "true"),
+    ]);
+    assert_errors(vec![
+        (
+            "head(list())",
+            (1, 6),
+            "Cannot take the head of an empty list",
+        ),
+        (
+            "tail(list())",
+            (1, 6),
+            "Cannot take the tail of an empty list",
+        ),
+        (
+            "nth(list(1) 1)",
+            (1, 13),
+            "Index 1 is out of bounds for length 1",
+        ),
+        (
+            "nth(list() 0)",
+            (1, 12),
+            "Index 0 is out of bounds for length 0",
+        ),
+        (
+            "nth(list(1) -(0 1))",
+            (1, 13),
+            "Expected a non-negative integer index",
+        ),
+        ("cons(1 2)", (1, 8), "Not a list"),
+        ("head(1)", (1, 6), "Not a list"),
+        ("append(list(1) 2)", (1, 16), "Not a list"),
+        ("isEmpty(\"\")", (1, 9), "Not a list"),
+        (
+            "filter(func(x x) list(1))",
+            (1, 8),
+            "Expected the filter function to return a boolean",
+        ),
+        ("map(1 list(1))", (1, 5), "1 is not a function"),
+        (
+            "map(func(x y x) list(1))",
+            (1, 5),
+            "Wrong number of arguments; expected 2, got 1",
+        ),
+        (
+            "cons(1)",
+            (1, 1),
+            "Wrong number of arguments; expected 2, got 1",
+        ),
+        (
+            "<(list(1) list(\"a\"))",
+            (1, 11),
+            "Cannot order list and list",
+        ),
+    ]);
+}
+
+#[test]
+fn dicts() {
+    assert_values(vec![
+        ("dict()", "{}"),
+        ("dict(\"a\" 1 \"b\" 2)", "{a 1 b 2}"),
+        ("get(dict(\"a\" 1) \"a\")", "1"),
+        ("get(dict(\"a\" 1) \"b\" 0)", "0"),
+        ("get(dict(\"a\" 1) \"a\" 0)", "1"),
+        ("put(dict(\"a\" 1) \"a\" 2)", "{a 2}"),
+        ("put(dict(\"a\" 1) \"b\" 2)", "{a 1 b 2}"),
+        ("remove(dict(\"a\" 1 \"b\" 2) \"a\")", "{b 2}"),
+        ("remove(dict(\"a\" 1) \"z\")", "{a 1}"),
+        ("has(dict(\"a\" 1) \"a\")", "true"),
+        ("has(dict(\"a\" 1) \"b\")", "false"),
+        // Numbers that are `==` are the same key, whatever their type
+        ("get(dict(1 \"x\") 1.0)", "x"),
+        ("get(dict(1.0 \"x\") 1)", "x"),
+        ("has(dict(/(1 2) 1) 0.5)", "true"),
+        ("remove(dict(1 \"x\") 1.0)", "{}"),
+        ("dict(1 \"int\" 1.0 \"double\")", "{1 double}"),
+        // The entry keeps the key it was first given, and takes the latest value
+        ("put(dict(1 \"x\") 1.0 \"y\")", "{1 y}"),
+        ("typeOf(head(keys(put(dict(1 \"x\") 1.0 \"y\"))))", "int"),
+        // Keys are listed in order: numbers, then strings, then booleans
+        (
+            "keys(dict(\"b\" 1 \"a\" 2 3 3 true 4 1.5 5 /(1 3) 6))",
+            "[1/3 1.5 3 a b true]",
+        ),
+        ("keys(dict(/(1.0 0.0) 1 /(-1.0 0.0) 2 0 3))", "[-inf 0 inf]"),
+        ("values(dict(\"b\" 1 \"a\" 2))", "[2 1]"),
+        ("entries(dict(\"b\" 1 \"a\" 2))", "[[a 2] [b 1]]"),
+        ("entries(dict())", "[]"),
+        // Dicts are values, so changing one leaves it as it was
+        (
+            "=(d dict(\"a\" 1)) put(d \"b\" 2) remove(d \"a\") d",
+            "{a 1}",
+        ),
+        ("==(dict(\"a\" 1 \"b\" 2) dict(\"b\" 2 \"a\" 1))", "true"),
+        ("==(dict(1 1) dict(1.0 1.0))", "true"),
+        ("==(dict() dict(\"a\" 1))", "false"),
+    ]);
+    assert_errors(vec![
+        ("get(dict(\"a\" 1) \"b\")", (1, 17), "Key b not found"),
+        ("dict(/(0.0 0.0) 1)", (1, 6), "Dict keys cannot be NaN"),
+        ("get(dict() /(0.0 0.0))", (1, 12), "Dict keys cannot be NaN"),
+        (
+            "dict(list() 1)",
+            (1, 6),
+            "Dict keys must be strings, numbers or booleans",
+        ),
+        (
+            "put(dict() nil 1)",
+            (1, 12),
+            "Dict keys must be strings, numbers or booleans",
+        ),
+        (
+            "has(dict() dict())",
+            (1, 12),
+            "Dict keys must be strings, numbers or booleans",
+        ),
+        (
+            "dict(\"a\")",
+            (1, 1),
+            "Wrong number of arguments; expected an even number, got 1",
+        ),
+        (
+            "dict(\"a\" 1 \"b\")",
+            (1, 1),
+            "Wrong number of arguments; expected an even number, got 3",
+        ),
+        (
+            "get(dict())",
+            (1, 1),
+            "Wrong number of arguments; expected 2 or 3, got 1",
+        ),
+        ("get(1 \"a\")", (1, 5), "Not a dict"),
+    ]);
+}
@@ -0,0 +1,60 @@
+use std::process::{Command, Output};
+
+fn lithp(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lithp"))
+        .args(args)
+        .output()
+        .expect("Could not run lithp")
+}
+
+/// Writes `source` to a file of its own and runs it.
+fn run_file(name: &str, source: &str) -> Output {
+    let path = std::env::temp_dir().join(format!("lithp-cli-{}-{}.lthp", std::process::id(), name));
+    std::fs::write(&path, source).unwrap();
+    let output = lithp(&[path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn exit_codes() {
+    let cases = vec![
+        ("print(1)", 0),
+        ("nth(list() 0)", 1),
+        ("+(1", 2),
+        (") )", 2),
+        ("=(f func(x x)) f(1 2)", 3),
+        ("+(1 \"a\")", 4),
+        ("undefinedVar", 5),
+        ("undefinedFn()", 6),
+        ("=(x 1) x()", 7),
+        ("toInt(1e300)", 9),
+        ("/(1 0)", 10),
+    ];
+    for (idx, (source, code)) in cases.into_iter().enumerate() {
+        let output = run_file(&idx.to_string(), source);
+        assert_eq!(output.status.code(), Some(code), "running {:?}", source);
+    }
+}
+
+#[test]
+fn unreadable_files_are_io_errors() {
+    let output = lithp(&["does/not/exist.lthp"]);
+    assert_eq!(output.status.code(), Some(8));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("Could not read does/not/exist.lthp: "),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn extra_arguments_print_usage() {
+    let output = lithp(&["a.lthp", "b.lthp"]);
+    assert_eq!(output.status.code(), Some(64));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Usage: lithp [file]\n"
+    );
+}
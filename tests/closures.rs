@@ -0,0 +1,112 @@
+use lithp::interpreter::to_string;
+use lithp::{Interpreter, Value};
+use std::rc::Rc;
+
+/// Runs each source in turn in one interpreter, returning what the last one evaluates to.
+fn eval_all(sources: &[&str]) -> Result<String, String> {
+    let mut interpreter = Interpreter::new();
+    let mut last = Value::Nil;
+    for source in sources {
+        last = interpreter
+            .eval_str(source)
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(to_string(&last))
+}
+
+#[test]
+fn functions_see_where_they_were_defined() {
+    let make_adder = "=(makeAdder func(n func(x +(x n))))";
+    let cases = vec![
+        (vec![make_adder, "=(add1 makeAdder(1))", "add1(2)"], "3"),
+        (
+            vec![
+                make_adder,
+                "=(add1 makeAdder(1)) =(add10 makeAdder(10))",
+                "list(add1(5) add10(5) add1(0))",
+            ],
+            "[6 15 1]",
+        ),
+        // Rebinding a variable later is seen by functions that were defined before it
+        (vec!["=(n 1) =(getN func(n))", "=(n 2)", "getN()"], "2"),
+        (
+            vec![
+                "=(x 1) =(getX func(x))",
+                "=(caller func(x getX()))",
+                "caller(5)",
+            ],
+            "1",
+        ),
+        (vec!["=(f func(x x))", "==(f f)"], "true"),
+    ];
+    for (sources, expected) in cases {
+        assert_eq!(
+            eval_all(&sources),
+            Ok(expected.to_owned()),
+            "running {:?}",
+            sources
+        );
+    }
+}
+
+#[test]
+fn functions_do_not_see_their_callers_variables() {
+    let sources = vec!["=(getX func(x))", "=(caller func(x getX()))", "caller(5)"];
+    assert_eq!(
+        eval_all(&sources),
+        Err("1:13: Undeclared variable x".to_owned())
+    );
+    let sources = vec![
+        "=(makeAdder func(n func(x +(x n))))",
+        "=(add1 makeAdder(1))",
+        "=(n 100)",
+        "add1(2)",
+    ];
+    assert_eq!(eval_all(&sources), Ok("3".to_owned()));
+}
+
+#[test]
+fn recursive_functions_do_not_keep_their_scope_alive() {
+    let mut interpreter = Interpreter::new();
+    let marker = interpreter
+        .eval_str("=(makeMarker func(func(x x))) makeMarker()")
+        .unwrap();
+    let marker_rc = match &marker {
+        Value::Closure(closure) => closure.clone(),
+        value => panic!("{:?} is not a function", value),
+    };
+    interpreter.set_var("marker", marker);
+    assert_eq!(Rc::strong_count(&marker_rc), 2);
+
+    // Each call binds a recursive function in its own scope, which also holds the marker
+    interpreter
+        .eval_str(
+            "=(bindLocal func(m nth(list(
+                =(countDown func(n cond(>(n 0) countDown(-(n 1)) m)))
+                countDown(3)
+            ) 1)))",
+        )
+        .unwrap();
+    for _ in 0..3 {
+        let result = interpreter.eval_str("bindLocal(marker)").unwrap();
+        assert_eq!(result, Value::Closure(marker_rc.clone()));
+    }
+    assert_eq!(Rc::strong_count(&marker_rc), 2);
+
+    drop(interpreter);
+    assert_eq!(Rc::strong_count(&marker_rc), 1);
+}
+
+#[test]
+fn returned_functions_are_dropped_with_the_interpreter() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("=(makeAdder func(n func(x +(x n)))) =(add1 makeAdder(1))")
+        .unwrap();
+    let add1 = match interpreter.get_var("add1") {
+        Some(Value::Closure(closure)) => Rc::downgrade(&closure),
+        value => panic!("{:?} is not a function", value),
+    };
+    drop(interpreter);
+    assert!(add1.upgrade().is_none());
+}
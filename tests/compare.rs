@@ -0,0 +1,208 @@
+use lithp::interpreter::{compare, equal};
+use lithp::value::{Dict, Key, List};
+use lithp::{Interpreter, LithpError, Value};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::cmp::Ordering;
+
+/// Whether an ordering satisfies a comparison builtin
+type Accept = fn(Ordering) -> bool;
+
+fn number() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any::<i32>().prop_map(Value::Int),
+        any::<i64>().prop_map(Value::Long),
+        any::<i64>().prop_map(|n| Value::BigInt(BigInt::from(n) * BigInt::from(u64::MAX))),
+        (any::<i32>(), 2..100i32).prop_map(|(numer, denom)| {
+            Value::Rational(BigRational::new(numer.into(), denom.into()))
+        }),
+        (-1e12..1e12f64).prop_map(Value::Double),
+        finite_or_infinite().prop_map(Value::Double),
+        near_2_pow_53(),
+    ]
+}
+
+/// Any double but NaN, including very large and very small ones.
+fn finite_or_infinite() -> impl Strategy<Value = f64> {
+    use proptest::num::f64::*;
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+}
+
+/// Integers of each type close to 2^53, above which not every integer is a double.
+fn near_2_pow_53() -> impl Strategy<Value = Value> {
+    (-4..=4i64, 0..3).prop_map(|(offset, kind)| {
+        let long = (1 << 53) + offset;
+        match kind {
+            0 => Value::Long(long),
+            1 => Value::BigInt(long.into()),
+            _ => Value::Double(long as f64),
+        }
+    })
+}
+
+fn scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        number(),
+        "[a-cé]{0,4}".prop_map(Value::String),
+        any::<bool>().prop_map(Value::Boolean),
+        Just(Value::Nil),
+        Just(Value::Builtin("+".to_owned())),
+    ]
+}
+
+/// Keys of every kind, some of which are equal to each other, such as `1` and `1.0`.
+fn dict_key() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        "[a-c]".prop_map(Value::String),
+        any::<bool>().prop_map(Value::Boolean),
+        (0..3).prop_map(Value::Int),
+        (0..3).prop_map(|n| Value::Double(f64::from(n))),
+        Just(Value::Double(f64::INFINITY)),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    scalar().prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(|items| Value::List(List::from(items))),
+            vec((dict_key(), inner), 0..3).prop_map(|pairs| {
+                let mut entries = Dict::new();
+                for (key, value) in pairs {
+                    let normalized = Key::new(&key).unwrap();
+                    entries.entry(normalized).or_insert((key, value));
+                }
+                Value::Dict(entries)
+            }),
+        ]
+    })
+}
+
+/// Numbers and strings can be ordered against others of their kind, and everything else only
+/// tested for equality.
+fn orderable(left: &Value, right: &Value) -> bool {
+    let is_number = |value: &Value| {
+        matches!(
+            value,
+            Value::Int(_)
+                | Value::Long(_)
+                | Value::BigInt(_)
+                | Value::Rational(_)
+                | Value::Double(_)
+        )
+    };
+    (is_number(left) && is_number(right))
+        || matches!((left, right), (Value::String(_), Value::String(_)))
+}
+
+fn eval_with(source: &str, left: &Value, right: &Value) -> Result<Value, LithpError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_var("a", left.clone());
+    interpreter.set_var("b", right.clone());
+    interpreter.eval_str(source)
+}
+
+proptest! {
+    #[test]
+    fn equality_is_reflexive(value in value()) {
+        prop_assert!(equal(&value, &value));
+    }
+
+    #[test]
+    fn compare_is_antisymmetric(left in value(), right in value()) {
+        prop_assert_eq!(compare(&left, &right), compare(&right, &left).map(Ordering::reverse));
+    }
+
+    #[test]
+    fn equality_is_transitive(a in near_2_pow_53(), b in near_2_pow_53(), c in near_2_pow_53()) {
+        if equal(&a, &b) && equal(&b, &c) {
+            prop_assert!(equal(&a, &c), "{:?} == {:?} == {:?}", a, b, c);
+        }
+    }
+
+    #[test]
+    fn numbers_are_totally_ordered(a in number(), b in number(), c in number()) {
+        let ab = compare(&a, &b).unwrap();
+        let bc = compare(&b, &c).unwrap();
+        if ab != Ordering::Greater && bc != Ordering::Greater {
+            prop_assert_ne!(compare(&a, &c).unwrap(), Ordering::Greater);
+        }
+    }
+
+    #[test]
+    fn numbers_compare_by_value_across_types(int in any::<i32>()) {
+        let forms = vec![
+            Value::Int(int),
+            Value::Long(int.into()),
+            Value::BigInt(int.into()),
+            Value::Rational(BigRational::from_integer(int.into())),
+            Value::Double(int.into()),
+        ];
+        for left in &forms {
+            for right in &forms {
+                prop_assert!(equal(left, right), "{:?} != {:?}", left, right);
+            }
+        }
+        let above = Value::Double(f64::from(int) + 0.5);
+        prop_assert_eq!(compare(&Value::Int(int), &above), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn strings_order_lexicographically(left in ".{0,6}", right in ".{0,6}") {
+        let ordering = compare(&Value::String(left.clone()), &Value::String(right.clone()));
+        prop_assert_eq!(ordering, Some(left.cmp(&right)));
+    }
+
+    #[test]
+    fn values_of_different_types_are_unequal(left in scalar(), right in scalar()) {
+        let same_type = std::mem::discriminant(&left) == std::mem::discriminant(&right);
+        if !same_type && !orderable(&left, &right) {
+            prop_assert_eq!(compare(&left, &right), None);
+        }
+    }
+
+    #[test]
+    fn equality_builtins_agree_with_equal(left in value(), right in value()) {
+        let expected = equal(&left, &right);
+        prop_assert_eq!(eval_with("==(a b)", &left, &right), Ok(Value::Boolean(expected)));
+        prop_assert_eq!(eval_with("!=(a b)", &left, &right), Ok(Value::Boolean(!expected)));
+    }
+
+    #[test]
+    fn ordering_builtins_agree_with_compare(left in scalar(), right in scalar()) {
+        let cases: Vec<(&str, Accept)> = vec![
+            ("<(a b)", |ordering| ordering == Ordering::Less),
+            (">(a b)", |ordering| ordering == Ordering::Greater),
+            ("<=(a b)", |ordering| ordering != Ordering::Greater),
+            (">=(a b)", |ordering| ordering != Ordering::Less),
+        ];
+        for (source, accept) in cases {
+            let result = eval_with(source, &left, &right);
+            if orderable(&left, &right) {
+                let expected = accept(compare(&left, &right).unwrap());
+                prop_assert_eq!(result, Ok(Value::Boolean(expected)), "{}", source);
+            } else {
+                prop_assert!(
+                    matches!(result, Err(LithpError::TypeMismatch { .. })),
+                    "{} gave {:?}",
+                    source,
+                    result
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn comparisons_need_an_argument() {
+    for name in &["==", "<", ">", "<=", ">="] {
+        let result = Interpreter::new().eval_str(&format!("{}()", name));
+        assert!(
+            matches!(result, Err(LithpError::Arity { got: 0, .. })),
+            "{}() gave {:?}",
+            name,
+            result
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use lithp::parse::Span;
+use lithp::value::List;
+use lithp::{Interpreter, LithpError, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn host_functions_capture_state() {
+    let mut interpreter = Interpreter::new();
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    interpreter.register_fn("log", move |args: &[Value]| {
+        log.borrow_mut().extend(args.iter().cloned());
+        Ok(Value::Int(args.len() as i32))
+    });
+    let result = interpreter.eval_str("=(n log(+(1 2) \"a\")) map(log list(true))");
+    assert_eq!(result.unwrap(), Value::List(List::from(vec![Value::Int(1)])));
+    assert_eq!(interpreter.get_var("n"), Some(Value::Int(2)));
+    assert_eq!(
+        *seen.borrow(),
+        vec![
+            Value::Int(3),
+            Value::String("a".to_owned()),
+            Value::Boolean(true)
+        ]
+    );
+}
+
+#[test]
+fn host_errors_are_placed_at_the_call() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_fn("fail", |_: &[Value]| {
+        Err(LithpError::Runtime {
+            message: "Failed".to_owned(),
+            span: Span::default(),
+        })
+    });
+    let err = interpreter
+        .eval_named("test", "=(x 1)\n  fail(x)")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "2:3: Failed");
+    assert_eq!(
+        interpreter.render_error(&err),
+        "test:2:3: Failed\n  |\n2 |   fail(x)\n  |   ^^^^"
+    );
+    let err = interpreter.eval_str("map(fail list(1))").unwrap_err();
+    assert_eq!(err.to_string(), "1:5: Failed");
+}
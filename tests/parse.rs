@@ -0,0 +1,131 @@
+use lithp::parse::{paren_depth, parse, Expr, ExprKind};
+use lithp::{Interpreter, LithpError};
+
+/// Writes `expr` back out as source, so that the shape of what was parsed is easy to compare.
+fn show(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Identifier(name) => name.clone(),
+        ExprKind::FuncCall(callee, args) => format!(
+            "{}({})",
+            show(callee),
+            args.iter().map(show).collect::<Vec<_>>().join(" ")
+        ),
+        ExprKind::StringLiteral(string) => format!("{:?}", string),
+        ExprKind::IntLiteral(int) => int.to_string(),
+        ExprKind::LongLiteral(long) => long.to_string(),
+        ExprKind::BigIntLiteral(big) => big.to_string(),
+        ExprKind::DoubleLiteral(double) => format!("{:?}", double),
+        ExprKind::BooleanLiteral(boolean) => boolean.to_string(),
+        ExprKind::NilLiteral => "nil".to_owned(),
+    }
+}
+
+/// Each error `parse` reports for `source`, as `line:col: message`.
+fn errors_of(source: &str) -> Vec<String> {
+    match parse(source.to_owned()) {
+        Ok(exprs) => panic!("{:?} parsed to {:?}", source, exprs),
+        Err(LithpError::Multiple { errors }) => errors.iter().map(|err| err.to_string()).collect(),
+        Err(err) => vec![err.to_string()],
+    }
+}
+
+#[test]
+fn exprs() {
+    let cases = vec![
+        ("", vec![]),
+        ("a 1 \"b\"", vec!["a", "1", "\"b\""]),
+        ("f()", vec!["f()"]),
+        ("+(1 *(2 3))", vec!["+(1 *(2 3))"]),
+        ("make(1)(2)", vec!["make(1)(2)"]),
+        ("f(g)()(h())", vec!["f(g)()(h())"]),
+        ("f (1) g", vec!["f(1)", "g"]),
+        ("nil 1.5 true", vec!["nil", "1.5", "true"]),
+    ];
+    for (source, expected) in cases {
+        let exprs = match parse(source.to_owned()) {
+            Ok(exprs) => exprs,
+            Err(err) => panic!("{:?} failed to parse: {}", source, err),
+        };
+        let shown = exprs.iter().map(show).collect::<Vec<_>>();
+        assert_eq!(shown, expected, "parsing {:?}", source);
+    }
+}
+
+#[test]
+fn errors() {
+    let cases = vec![
+        ("foo(", vec![(1, 4, "Missing closing parenthesis")]),
+        ("f(g(1)", vec![(1, 2, "Missing closing parenthesis")]),
+        (")", vec![(1, 1, "Unexpected closing parenthesis")]),
+        (
+            "(x)",
+            vec![(1, 1, "Expected a function before parenthesis")],
+        ),
+        (
+            "1(2)",
+            vec![(1, 2, "Expected a function before parenthesis")],
+        ),
+        (
+            ") (x) foo(1))",
+            vec![
+                (1, 1, "Unexpected closing parenthesis"),
+                (1, 3, "Expected a function before parenthesis"),
+                (1, 13, "Unexpected closing parenthesis"),
+            ],
+        ),
+        ("f(\"a)", vec![(1, 3, "Unterminated string literal")]),
+    ];
+    for (source, expected) in cases {
+        let expected = expected
+            .into_iter()
+            .map(|(line, col, message)| format!("{}:{}: {}", line, col, message))
+            .collect::<Vec<_>>();
+        assert_eq!(errors_of(source), expected, "parsing {:?}", source);
+    }
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let source = format!("{}{}", "f(".repeat(10_000), ")".repeat(10_000));
+    let errors = errors_of(&source);
+    assert_eq!(errors, vec!["1:514: Calls are nested too deeply"]);
+}
+
+#[test]
+fn long_call_chains_are_an_error() {
+    let source = format!("f{}", "()".repeat(100_000));
+    let errors = errors_of(&source);
+    assert_eq!(errors, vec!["1:514: Calls are nested too deeply"]);
+    let err = Interpreter::new().eval_str(&source).unwrap_err();
+    assert!(matches!(err, LithpError::ParseError { .. }), "{:?}", err);
+}
+
+#[test]
+fn paren_depths() {
+    let cases = vec![
+        ("", 0),
+        ("f(1)", 0),
+        ("f(", 1),
+        ("f(g(", 2),
+        ("f(\n  g(1)\n", 1),
+        ("f(\"(\"", 1),
+        ("f(\")\")", 0),
+        ("f(\"\\\")\"", 1),
+        ("f(\"\\\\\")", 0),
+        ("print(\"a", 2),
+        ("\"a\nb\"", 0),
+        ("f(; )\n", 1),
+        ("f() ; (\n", 0),
+        ("#| ( |#", 0),
+        ("#| #| ) |# ( |#", 0),
+        ("#| a", 1),
+        ("#| #| a |#", 1),
+        ("\"#|\"", 0),
+        ("f())", -1),
+        (") f(", -1),
+        ("f()) g(", -1),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(paren_depth(input), expected, "depth of {:?}", input);
+    }
+}
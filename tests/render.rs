@@ -0,0 +1,69 @@
+use lithp::Interpreter;
+
+/// Runs each source in turn in one interpreter, rendering the error that the last one fails with.
+fn render_last(sources: &[&str]) -> String {
+    let mut interpreter = Interpreter::new();
+    let (last, earlier) = sources.split_last().unwrap();
+    for source in earlier {
+        interpreter.eval_named("<repl>", source).unwrap();
+    }
+    match interpreter.eval_named("<repl>", last) {
+        Ok(value) => panic!("{:?} evaluated to {:?}", last, value),
+        Err(err) => interpreter.render_error(&err),
+    }
+}
+
+#[test]
+fn excerpts() {
+    let cases = vec![
+        (
+            vec!["=(x 1)\nprint(+(x \"a\"))"],
+            "<repl>:2:11: Not a numeric value
+  |
+2 | print(+(x \"a\"))
+  |           ^^^",
+        ),
+        (
+            vec![") (x)"],
+            "<repl>:1:1: Unexpected closing parenthesis
+  |
+1 | ) (x)
+  | ^
+<repl>:1:3: Expected a function before parenthesis
+  |
+1 | ) (x)
+  |   ^",
+        ),
+        // Errors in a function are shown where it was defined, not where it was called
+        (
+            vec!["=(f func(x +(x \"a\")))", "f(1)"],
+            "<repl>:1:16: Not a numeric value
+  |
+1 | =(f func(x +(x \"a\")))
+  |                ^^^",
+        ),
+        (
+            vec!["=(f func(x +(x \"a\")))", "\n\n=(g f)", "g(1)"],
+            "<repl>:1:16: Not a numeric value
+  |
+1 | =(f func(x +(x \"a\")))
+  |                ^^^",
+        ),
+    ];
+    for (sources, expected) in cases {
+        assert_eq!(render_last(&sources), expected, "running {:?}", sources);
+    }
+}
+
+#[test]
+fn errors_without_a_position() {
+    let mut interpreter = Interpreter::new();
+    let err = interpreter.eval_file("does/not/exist.lthp").unwrap_err();
+    let rendered = interpreter.render_error(&err);
+    assert!(
+        rendered.starts_with("Could not read does/not/exist.lthp: "),
+        "{}",
+        rendered
+    );
+    assert_eq!(err.render("exist.lthp", ""), format!("exist.lthp: {}", err));
+}
@@ -0,0 +1,46 @@
+use lithp::{Interpreter, Value};
+
+/// Runs `source` and then `call`, returning what `call` evaluates to.
+fn eval_after(source: &str, call: &str) -> Value {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str(source).unwrap();
+    interpreter.eval_str(call).unwrap()
+}
+
+#[test]
+fn loop_example_runs_a_million_times() {
+    let loop_example = include_str!("../examples/loop.lthp");
+    assert_eq!(eval_after(loop_example, "loop(1000000)"), Value::Int(0));
+}
+
+#[test]
+fn cond_branches_are_tail_calls() {
+    let count_down = "=(countDown func(n cond(>(n 0) countDown(-(n 1)) \"done\")))";
+    assert_eq!(
+        eval_after(count_down, "countDown(100000)"),
+        Value::String("done".to_owned())
+    );
+}
+
+#[test]
+fn switch_branches_are_tail_calls() {
+    let count_down = "=(countDown func(n switch(>(n 0) true countDown(-(n 1)) \"done\")))";
+    assert_eq!(
+        eval_after(count_down, "countDown(100000)"),
+        Value::String("done".to_owned())
+    );
+}
+
+#[test]
+fn fallback_branches_are_tail_calls() {
+    let count_down = "=(countDown func(n cond(<=(n 0) \"done\" countDown(-(n 1)))))";
+    assert_eq!(
+        eval_after(count_down, "countDown(100000)"),
+        Value::String("done".to_owned())
+    );
+    let count_down = "=(countDown func(n switch(n 0 \"done\" countDown(-(n 1)))))";
+    assert_eq!(
+        eval_after(count_down, "countDown(100000)"),
+        Value::String("done".to_owned())
+    );
+}
@@ -0,0 +1,190 @@
+use lithp::parse::{tokenize, Span, TokenKind};
+use num_bigint::BigInt;
+use TokenKind::*;
+
+fn ident(name: &str) -> TokenKind {
+    Identifier(name.to_owned())
+}
+
+fn string(text: &str) -> TokenKind {
+    StringLiteral(text.to_owned())
+}
+
+fn kinds(source: &str) -> Vec<TokenKind> {
+    match tokenize(source.to_owned()) {
+        Ok(tokens) => tokens.into_iter().map(|token| token.kind).collect(),
+        Err(err) => panic!("{:?} failed to tokenize: {}", source, err),
+    }
+}
+
+#[test]
+fn tokens() {
+    let cases = vec![
+        ("", vec![]),
+        ("  \t\n ", vec![]),
+        ("a", vec![ident("a")]),
+        (
+            "a(b)c",
+            vec![ident("a"), GroupLeft, ident("b"), GroupRight, ident("c")],
+        ),
+        ("f()", vec![ident("f"), GroupLeft, GroupRight]),
+        (
+            "+(1 2)",
+            vec![
+                ident("+"),
+                GroupLeft,
+                IntLiteral(1),
+                IntLiteral(2),
+                GroupRight,
+            ],
+        ),
+        (
+            "1(2)3",
+            vec![
+                IntLiteral(1),
+                GroupLeft,
+                IntLiteral(2),
+                GroupRight,
+                IntLiteral(3),
+            ],
+        ),
+        (
+            "f(1)(2)",
+            vec![
+                ident("f"),
+                GroupLeft,
+                IntLiteral(1),
+                GroupRight,
+                GroupLeft,
+                IntLiteral(2),
+                GroupRight,
+            ],
+        ),
+        ("1.5)", vec![DoubleLiteral(1.5), GroupRight]),
+        (
+            "-2.5 .5 +.5 5. 1e3 -1.5e-3",
+            vec![
+                DoubleLiteral(-2.5),
+                DoubleLiteral(0.5),
+                DoubleLiteral(0.5),
+                DoubleLiteral(5.0),
+                DoubleLiteral(1000.0),
+                DoubleLiteral(-0.0015),
+            ],
+        ),
+        // Words that Rust would read as doubles are identifiers
+        (
+            "inf -inf +infinity nan NaN infinity",
+            vec![
+                ident("inf"),
+                ident("-inf"),
+                ident("+infinity"),
+                ident("nan"),
+                ident("NaN"),
+                ident("infinity"),
+            ],
+        ),
+        (". - e5", vec![ident("."), ident("-"), ident("e5")]),
+        ("-3", vec![IntLiteral(-3)]),
+        ("1_000", vec![ident("1_000")]),
+        ("3000000000", vec![LongLiteral(3_000_000_000)]),
+        (
+            "100000000000000000000",
+            vec![BigIntLiteral(
+                "100000000000000000000".parse::<BigInt>().unwrap(),
+            )],
+        ),
+        (
+            "true false",
+            vec![BooleanLiteral(true), BooleanLiteral(false)],
+        ),
+        ("nil nils", vec![NilLiteral, ident("nils")]),
+        ("\"a\"\"b\"", vec![string("a"), string("b")]),
+        ("\"a\" \"b\"", vec![string("a"), string("b")]),
+        ("x\"a\"y", vec![ident("x"), string("a"), ident("y")]),
+        (
+            "f(\"a\")",
+            vec![ident("f"), GroupLeft, string("a"), GroupRight],
+        ),
+        ("\"\"", vec![string("")]),
+        ("\"(a b)\"", vec![string("(a b)")]),
+        ("\"a ; b #| c |#\"", vec![string("a ; b #| c |#")]),
+        ("\"\\\" \\\\ \\n \\t\"", vec![string("\" \\ \n \t")]),
+        ("\"\\u{48}\\u{1F600}\"", vec![string("H\u{1F600}")]),
+        ("\"héllo ✓\"", vec![string("héllo ✓")]),
+        ("\"a\nb\"", vec![string("a\nb")]),
+        ("é(ü)", vec![ident("é"), GroupLeft, ident("ü"), GroupRight]),
+        ("a\tb", vec![ident("a"), ident("b")]),
+        ("a\r\nb", vec![ident("a"), ident("b")]),
+        (
+            "a\u{a0}b\u{2003}c",
+            vec![ident("a"), ident("b"), ident("c")],
+        ),
+        ("a ; comment (\nb", vec![ident("a"), ident("b")]),
+        ("a;b", vec![ident("a")]),
+        ("a#|x|#b", vec![ident("a"), ident("b")]),
+        ("#| a #| b |# c |# d", vec![ident("d")]),
+        ("a#b", vec![ident("a#b")]),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(kinds(source), expected, "tokenizing {:?}", source);
+    }
+}
+
+#[test]
+fn spans() {
+    let cases = vec![
+        ("abc", vec![(1, 1, 3)]),
+        ("a(b)", vec![(1, 1, 1), (1, 2, 1), (1, 3, 1), (1, 4, 1)]),
+        ("\"é✓\" x", vec![(1, 1, 4), (1, 6, 1)]),
+        ("a\r\n  b", vec![(1, 1, 1), (2, 3, 1)]),
+        ("#| \n |# x", vec![(2, 5, 1)]),
+    ];
+    for (source, expected) in cases {
+        let spans = tokenize(source.to_owned())
+            .unwrap()
+            .into_iter()
+            .map(|token| token.span)
+            .map(|Span { line, col, len, .. }| (line, col, len))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, expected, "spans of {:?}", source);
+    }
+}
+
+#[test]
+fn errors() {
+    let cases = vec![
+        ("\"abc", "Unterminated string literal", (1, 1)),
+        ("a \"b\\", "Unterminated escape sequence", (1, 5)),
+        ("\"\\q\"", "Unknown escape sequence \\q", (1, 2)),
+        (
+            "\"\\u{}\"",
+            "Expected \\u{...} with one to six hex digits",
+            (1, 2),
+        ),
+        (
+            "\"\\u{1234567}\"",
+            "Expected \\u{...} with one to six hex digits",
+            (1, 2),
+        ),
+        ("\"\\u{d800}\"", "Invalid unicode escape", (1, 2)),
+        ("a\n  #| x", "Unterminated block comment", (2, 3)),
+        ("a |# b", "Unmatched end of block comment", (1, 3)),
+        ("f(a, b)", "Unexpected character ','", (1, 4)),
+        ("[1 2]", "Unexpected character '['", (1, 1)),
+        ("x\n{", "Unexpected character '{'", (2, 1)),
+        ("a\u{7}", "Unexpected character '\\u{7}'", (1, 2)),
+    ];
+    for (source, message, (line, col)) in cases {
+        let err = match tokenize(source.to_owned()) {
+            Ok(tokens) => panic!("{:?} tokenized to {:?}", source, tokens),
+            Err(err) => err,
+        };
+        assert_eq!(
+            err.to_string(),
+            format!("{}:{}: {}", line, col, message),
+            "tokenizing {:?}",
+            source
+        );
+    }
+}